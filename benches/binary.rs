@@ -0,0 +1,38 @@
+use std::fmt::Write;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use edn_rs::binary::{from_bytes, to_bytes};
+use edn_rs::edn_reader::read_str;
+
+/// A config-like document of a few thousand nested maps.
+fn document() -> String {
+    let mut text = String::from("[");
+    for i in 0..2000 {
+        write!(
+            text,
+            r#"{{:id {i}, :name "service-{i}", :ns/enabled {}, :weight {}.25,
+               :tags #{{:a :b :c{}}}, :ports [8080 8081 {}], :owner {{:team "infra" :oncall nil}}}}
+            "#,
+            i % 2 == 0,
+            i % 10,
+            i % 7,
+            9000 + i
+        )
+        .unwrap();
+    }
+    text.push(']');
+    text
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let text = document();
+    let bytes = to_bytes(&read_str(text.clone()).unwrap());
+
+    let mut group = c.benchmark_group("decode");
+    group.bench_function("read_str", |b| b.iter(|| read_str(text.clone()).unwrap()));
+    group.bench_function("from_bytes", |b| b.iter(|| from_bytes(&bytes).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
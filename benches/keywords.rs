@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use edn_rs::edn_reader::read_str;
+use edn_rs::Keyword;
+
+const CORPUS: &str = "examples/edn-tests/performance";
+
+/// Threads reading at once in `read/corpus threaded`, which share the interner.
+const THREADS: usize = 4;
+
+/// The `.edn` files of the performance corpus, or a document of repeated keywords like
+/// `:db/id` when it is not checked out.
+fn corpus() -> Vec<String> {
+    let files: Vec<String> = walkdir::WalkDir::new(CORPUS)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "edn"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .collect();
+    if !files.is_empty() {
+        return files;
+    }
+    eprintln!(
+        "warning: {CORPUS} is not checked out (git submodule update --init), \
+         reading a generated document instead"
+    );
+    let mut text = String::from("[");
+    for i in 0..5000 {
+        write!(
+            text,
+            r#"{{:db/id {i} :user/name "user-{i}" :user/email "u{i}@example.com"
+               :user/roles #{{:role/admin :role/dev}} :user/team {{:db/id {}}}}}
+            "#,
+            i % 50
+        )
+        .unwrap();
+    }
+    text.push(']');
+    vec![text]
+}
+
+fn read_corpus(corpus: &[String]) {
+    for text in corpus {
+        read_str(text.clone()).unwrap();
+    }
+}
+
+fn bench_read(c: &mut Criterion) {
+    let corpus = corpus();
+    let mut group = c.benchmark_group("read");
+    group.bench_function("corpus", |b| b.iter(|| read_corpus(&corpus)));
+    group.bench_function("corpus threaded", |b| {
+        b.iter(|| {
+            std::thread::scope(|scope| {
+                for _ in 0..THREADS {
+                    scope.spawn(|| read_corpus(&corpus));
+                }
+            })
+        })
+    });
+    group.finish();
+}
+
+/// Keyword lookups against looking up their text, which is how keywords were stored before
+/// they were interned.
+fn bench_lookup(c: &mut Criterion) {
+    let names: Vec<String> = (0..200).map(|i| format!("user/attribute-{i}")).collect();
+    let keywords: Vec<Keyword> = names
+        .iter()
+        .map(|name| Keyword::new(name).unwrap())
+        .collect();
+    let keyword_set: HashSet<&Keyword> = keywords.iter().collect();
+    let string_set: HashSet<&String> = names.iter().collect();
+
+    let mut group = c.benchmark_group("lookup");
+    group.bench_function("interned", |b| {
+        b.iter(|| keywords.iter().filter(|k| keyword_set.contains(k)).count())
+    });
+    group.bench_function("string", |b| {
+        b.iter(|| names.iter().filter(|s| string_set.contains(s)).count())
+    });
+    group.bench_function("interned clone", |b| b.iter(|| keywords.clone()));
+    group.bench_function("string clone", |b| b.iter(|| names.clone()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_read, bench_lookup);
+criterion_main!(benches);
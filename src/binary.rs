@@ -0,0 +1,463 @@
+//! A compact, self-describing binary encoding of [`Edn`] values.
+//!
+//! The bytes start with the magic `EDNB` and a version byte. Each value is a tag byte followed
+//! by its payload. Lengths and counts are unsigned LEB128 varints and integers are zigzag
+//! encoded, so small numbers take one byte. Every value, including the scale of big decimals,
+//! the bits of floats and metadata, comes back exactly as it was written.
+
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+
+use bigdecimal::BigDecimal;
+use num::{BigInt, BigRational, Zero};
+
+use crate::edn_inst::Inst;
+use crate::edn_reader::{Edn, MAX_DEPTH};
+use crate::edn_symbol::{Keyword, Symbol};
+use crate::edn_uuid::Uuid;
+
+const MAGIC: &[u8; 4] = b"EDNB";
+const VERSION: u8 = 1;
+
+const NIL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const STRING: u8 = 3;
+const CHAR: u8 = 4;
+const SYMBOL: u8 = 5;
+const KEYWORD: u8 = 6;
+const INT: u8 = 7;
+const BIGINT: u8 = 8;
+const FLOAT: u8 = 9;
+const BIGDECIMAL: u8 = 10;
+const BIGRATIONAL: u8 = 11;
+const LIST: u8 = 12;
+const VEC: u8 = 13;
+const SET: u8 = 14;
+const MAP: u8 = 15;
+const INST: u8 = 16;
+const UUID: u8 = 17;
+const TAGGED: u8 = 18;
+const META: u8 = 19;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    UnexpectedEnd,
+    InvalidType(u8),
+    InvalidValue(&'static str),
+    NestingTooDeep(usize),
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::InvalidHeader => write!(f, "Not binary EDN"),
+            BinaryError::UnsupportedVersion(v) => write!(f, "Unsupported binary EDN version: {v}"),
+            BinaryError::UnexpectedEnd => write!(f, "Unexpected end of input"),
+            BinaryError::InvalidType(t) => write!(f, "Invalid type byte: {t}"),
+            BinaryError::InvalidValue(what) => write!(f, "Invalid {what}"),
+            BinaryError::NestingTooDeep(depth) => {
+                write!(f, "Values nested deeper than {depth} levels")
+            }
+            BinaryError::TrailingBytes(n) => write!(f, "{n} trailing bytes after the value"),
+        }
+    }
+}
+
+impl Error for BinaryError {}
+
+pub fn to_bytes(edn: &Edn) -> Vec<u8> {
+    let mut out = Vec::from(*MAGIC);
+    out.push(VERSION);
+    write(&mut out, edn);
+    out
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<Edn, BinaryError> {
+    let body = bytes
+        .strip_prefix(MAGIC)
+        .ok_or(BinaryError::InvalidHeader)?;
+    let (&version, body) = body.split_first().ok_or(BinaryError::InvalidHeader)?;
+    if version != VERSION {
+        return Err(BinaryError::UnsupportedVersion(version));
+    }
+    let mut decoder = Decoder {
+        bytes: body,
+        depth: 0,
+    };
+    let edn = decoder.read()?;
+    match decoder.bytes.len() {
+        0 => Ok(edn),
+        n => Err(BinaryError::TrailingBytes(n)),
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_int(out: &mut Vec<u8>, n: i64) {
+    write_varint(out, ((n << 1) ^ (n >> 63)) as u64);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_big_int(out: &mut Vec<u8>, n: &BigInt) {
+    write_bytes(out, &n.to_signed_bytes_le());
+}
+
+fn write_all<'e>(out: &mut Vec<u8>, items: impl ExactSizeIterator<Item = &'e Edn>) {
+    write_varint(out, items.len() as u64);
+    items.for_each(|edn| write(out, edn));
+}
+
+fn write_map(out: &mut Vec<u8>, map: &BTreeMap<Edn, Edn>) {
+    write_varint(out, map.len() as u64);
+    for (key, value) in map {
+        write(out, key);
+        write(out, value);
+    }
+}
+
+fn write(out: &mut Vec<u8>, edn: &Edn) {
+    match edn {
+        Edn::Nil => out.push(NIL),
+        Edn::Bool(false) => out.push(FALSE),
+        Edn::Bool(true) => out.push(TRUE),
+        Edn::String(s) => {
+            out.push(STRING);
+            write_bytes(out, s.as_bytes());
+        }
+        Edn::Char(c) => {
+            out.push(CHAR);
+            write_varint(out, u64::from(*c));
+        }
+        Edn::Symbol(s) => {
+            out.push(SYMBOL);
+            write_bytes(out, s.as_str().as_bytes());
+        }
+        Edn::Keyword(s) => {
+            out.push(KEYWORD);
+            write_bytes(out, s.as_str().as_bytes());
+        }
+        Edn::Int(n) => {
+            out.push(INT);
+            write_int(out, *n);
+        }
+        Edn::BigInt(n) => {
+            out.push(BIGINT);
+            write_big_int(out, n);
+        }
+        Edn::Float(n) => {
+            out.push(FLOAT);
+            out.extend_from_slice(&n.to_bits().to_le_bytes());
+        }
+        Edn::BigDecimal(n) => {
+            let (digits, scale) = n.as_bigint_and_exponent();
+            out.push(BIGDECIMAL);
+            write_big_int(out, &digits);
+            write_int(out, scale);
+        }
+        Edn::BigRational(n) => {
+            out.push(BIGRATIONAL);
+            write_big_int(out, n.numer());
+            write_big_int(out, n.denom());
+        }
+        Edn::List(vec) => {
+            out.push(LIST);
+            write_all(out, vec.iter());
+        }
+        Edn::Vec(vec) => {
+            out.push(VEC);
+            write_all(out, vec.iter());
+        }
+        Edn::Set(set) => {
+            out.push(SET);
+            write_all(out, set.iter());
+        }
+        Edn::Map(map) => {
+            out.push(MAP);
+            write_map(out, map);
+        }
+        Edn::Inst(inst) => {
+            out.push(INST);
+            write_int(out, inst.timestamp());
+            write_varint(out, inst.subsec_nanos().into());
+        }
+        Edn::Uuid(uuid) => {
+            out.push(UUID);
+            out.extend_from_slice(uuid.as_bytes());
+        }
+        Edn::TaggedElement(tag, edn) => {
+            out.push(TAGGED);
+            write_bytes(out, tag.as_bytes());
+            write(out, edn);
+        }
+        Edn::WithMeta(meta, edn) => {
+            out.push(META);
+            write_map(out, meta);
+            write(out, edn);
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], BinaryError> {
+        if self.bytes.len() < n {
+            return Err(BinaryError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, BinaryError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(BinaryError::InvalidValue("varint"))
+    }
+
+    fn int(&mut self) -> Result<i64, BinaryError> {
+        let n = self.varint()?;
+        Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+    }
+
+    /// A length or count. Every element takes at least one byte, so anything longer than the
+    /// rest of the input is an error instead of a huge allocation.
+    fn len(&mut self) -> Result<usize, BinaryError> {
+        match usize::try_from(self.varint()?) {
+            Ok(len) if len <= self.bytes.len() => Ok(len),
+            _ => Err(BinaryError::UnexpectedEnd),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], BinaryError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, BinaryError> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BinaryError::InvalidValue("UTF-8"))
+    }
+
+    fn big_int(&mut self) -> Result<BigInt, BinaryError> {
+        Ok(BigInt::from_signed_bytes_le(self.bytes()?))
+    }
+
+    fn items(&mut self) -> Result<Vec<Edn>, BinaryError> {
+        let len = self.len()?;
+        (0..len).map(|_| self.read()).collect()
+    }
+
+    fn map(&mut self) -> Result<BTreeMap<Edn, Edn>, BinaryError> {
+        let len = self.len()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..len {
+            let key = self.read()?;
+            if map.insert(key, self.read()?).is_some() {
+                return Err(BinaryError::InvalidValue("map with duplicate keys"));
+            }
+        }
+        Ok(map)
+    }
+
+    fn set(&mut self) -> Result<BTreeSet<Edn>, BinaryError> {
+        let mut set = BTreeSet::new();
+        for edn in self.items()? {
+            if !set.insert(edn) {
+                return Err(BinaryError::InvalidValue("set with duplicate elements"));
+            }
+        }
+        Ok(set)
+    }
+
+    fn read(&mut self) -> Result<Edn, BinaryError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(BinaryError::NestingTooDeep(MAX_DEPTH));
+        }
+        self.depth += 1;
+        let edn = self.read_value();
+        self.depth -= 1;
+        edn
+    }
+
+    fn read_value(&mut self) -> Result<Edn, BinaryError> {
+        Ok(match self.byte()? {
+            NIL => Edn::Nil,
+            FALSE => Edn::Bool(false),
+            TRUE => Edn::Bool(true),
+            STRING => Edn::String(self.string()?),
+            CHAR => {
+                let c = u32::try_from(self.varint()?).ok().and_then(char::from_u32);
+                Edn::Char(c.ok_or(BinaryError::InvalidValue("char"))?)
+            }
+            SYMBOL => Edn::Symbol(
+                Symbol::new(&self.string()?).map_err(|_| BinaryError::InvalidValue("symbol"))?,
+            ),
+            KEYWORD => Edn::Keyword(
+                Keyword::new(&self.string()?).map_err(|_| BinaryError::InvalidValue("keyword"))?,
+            ),
+            INT => Edn::Int(self.int()?),
+            BIGINT => Edn::BigInt(self.big_int()?),
+            FLOAT => {
+                let bits = self.take(8)?.try_into().unwrap();
+                Edn::Float(f64::from_bits(u64::from_le_bytes(bits)).into())
+            }
+            BIGDECIMAL => {
+                let digits = self.big_int()?;
+                Edn::BigDecimal(BigDecimal::new(digits, self.int()?))
+            }
+            BIGRATIONAL => {
+                let numer = self.big_int()?;
+                let denom = self.big_int()?;
+                if denom.is_zero() {
+                    return Err(BinaryError::InvalidValue("ratio"));
+                }
+                Edn::BigRational(BigRational::new(numer, denom))
+            }
+            LIST => Edn::List(self.items()?),
+            VEC => Edn::Vec(self.items()?),
+            SET => Edn::Set(self.set()?),
+            MAP => Edn::Map(self.map()?),
+            INST => {
+                let seconds = self.int()?;
+                let nanos = u32::try_from(self.varint()?).ok();
+                let inst = nanos.and_then(|nanos| Inst::from_timestamp(seconds, nanos));
+                Edn::Inst(inst.ok_or(BinaryError::InvalidValue("inst"))?)
+            }
+            UUID => Edn::Uuid(Uuid::from_bytes(self.take(16)?.try_into().unwrap())),
+            TAGGED => {
+                let tag = self.string()?;
+                Edn::TaggedElement(tag, Box::new(self.read()?))
+            }
+            META => {
+                let meta = self.map()?;
+                self.read()?.with_meta(meta)
+            }
+            t => return Err(BinaryError::InvalidType(t)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::edn_reader::{read_str_with_options, ReaderOptions};
+
+    fn edn(s: &str) -> Edn {
+        read_str_with_options(s, ReaderOptions::new().keep_unknown_tags()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = edn(
+            r#"^{:doc "x"} [nil true false "s" "ünï" \c \☃ sym ns/sym :kw :ns/kw 0 -1 300
+            -9223372036854775808 123456789012345678901234567890N 1.5 -0.0 ##NaN ##-Inf
+            1.50M -2/4 (1 2) #{1 2} {:a 1, "b" [2], [3] ^:m y}
+            #inst "1969-12-31T23:59:59.000000001-00:00"
+            #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6" #my/tag {:x y}]"#,
+        );
+        let bytes = to_bytes(&value);
+        assert_eq!(&bytes[..5], b"EDNB\x01");
+        let back = from_bytes(&bytes).unwrap();
+        assert_eq!(format!("{back:#}"), format!("{value:#}"));
+        assert_eq!(back, value);
+        assert_eq!(to_bytes(&back), bytes);
+    }
+
+    #[test]
+    fn test_meta_like_reader() {
+        let meta = |s: &str| edn(s).meta().cloned().unwrap_or_default();
+        let x = || Box::new(edn("x"));
+        let empty = Edn::WithMeta(meta("y"), x());
+        assert!(matches!(from_bytes(&to_bytes(&empty)), Ok(Edn::Symbol(_))));
+        let nested = Edn::WithMeta(meta("^:a y"), Box::new(Edn::WithMeta(meta("^:b y"), x())));
+        let back = from_bytes(&to_bytes(&nested)).unwrap();
+        assert_eq!(format!("{back:#}"), format!("{:#}", edn("^:a x")));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(from_bytes(b"EDN"), Err(BinaryError::InvalidHeader));
+        assert_eq!(
+            from_bytes(b"EDNB\x02\x00"),
+            Err(BinaryError::UnsupportedVersion(2))
+        );
+        assert_eq!(from_bytes(b"EDNB\x01"), Err(BinaryError::UnexpectedEnd));
+        assert_eq!(
+            from_bytes(b"EDNB\x01\x00\x00"),
+            Err(BinaryError::TrailingBytes(1))
+        );
+        assert_eq!(
+            from_bytes(b"EDNB\x01\xff"),
+            Err(BinaryError::InvalidType(0xff))
+        );
+        assert_eq!(
+            from_bytes(b"EDNB\x01\x0d\xff\xff\xff\xff\x0f"),
+            Err(BinaryError::UnexpectedEnd)
+        );
+        assert_eq!(
+            from_bytes(&[b"EDNB\x01".as_slice(), &[MAP, 2, INT, 2, NIL, INT, 2, TRUE]].concat()),
+            Err(BinaryError::InvalidValue("map with duplicate keys"))
+        );
+        assert_eq!(
+            from_bytes(
+                &[
+                    b"EDNB\x01".as_slice(),
+                    &[META, 2, NIL, NIL, NIL, NIL, VEC, 0]
+                ]
+                .concat()
+            ),
+            Err(BinaryError::InvalidValue("map with duplicate keys"))
+        );
+        assert_eq!(
+            from_bytes(&[b"EDNB\x01".as_slice(), &[SET, 2, TRUE, TRUE]].concat()),
+            Err(BinaryError::InvalidValue("set with duplicate elements"))
+        );
+        let mut deep = b"EDNB\x01".to_vec();
+        deep.extend([VEC, 1].repeat(MAX_DEPTH + 1));
+        deep.push(NIL);
+        assert_eq!(
+            from_bytes(&deep),
+            Err(BinaryError::NestingTooDeep(MAX_DEPTH))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_arbitrary_bytes_do_not_panic(bytes in proptest::collection::vec(any::<u8>(), 0..64)) {
+            let mut input = b"EDNB\x01".to_vec();
+            input.extend(bytes);
+            let _ = from_bytes(&input);
+        }
+    }
+}
@@ -0,0 +1,767 @@
+//! A lossless concrete syntax tree of EDN source.
+//!
+//! Unlike the reader, which drops whitespace, commas, comments and `#_` discards, the tree
+//! keeps every char of the source, so printing a [`Document`] gives back the exact text it was
+//! parsed from. Edits with [`Document::set_in`] only replace the nodes they touch and leave the
+//! formatting of everything else alone:
+//!
+//! ```
+//! use edn_rs::cst::Document;
+//! use edn_rs::edn_reader::read_str;
+//!
+//! let mut doc = Document::parse("{:paths [\"src\"] ; sources\n :deps {}}").unwrap();
+//! let path = [read_str(":paths".to_string()).unwrap()];
+//! doc.set_in(&path, &read_str("[\"src\" \"test\"]".to_string()).unwrap()).unwrap();
+//! assert_eq!(doc.to_string(), "{:paths [\"src\" \"test\"] ; sources\n :deps {}}");
+//! ```
+
+use core::fmt;
+use std::error::Error;
+
+use crate::edn_error::{EdnError, ErrorKind, Position};
+use crate::edn_reader::{qualify_key, read_str_with_options, Edn, Reader, ReaderOptions};
+use crate::edn_writer::{self, PrintOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// Whitespace and commas.
+    Whitespace,
+    /// A `;` comment up to the end of the line, without the line break.
+    Comment,
+    /// `#_` followed by the discarded form.
+    Discard,
+    /// An atom: nil, a boolean, number, string, char, symbol or keyword.
+    Token,
+    /// Opening and closing delimiters and the prefixes `^`, `#^`, `##`, `#:` and `#tag`.
+    Delimiter,
+    List,
+    Vector,
+    Map,
+    Set,
+    /// A `#:ns{...}` map, whose unqualified keys get the namespace.
+    NamespaceMap,
+    /// `#tag` followed by the tagged element.
+    Tagged,
+    /// `^` followed by the metadata and the form it applies to.
+    Meta,
+    /// `##` followed by `Inf`, `-Inf` or `NaN`.
+    SymbolicValue,
+}
+
+/// A node of the tree. Leaves hold their source text, other nodes hold children whose texts
+/// concatenate to the source of the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    kind: NodeKind,
+    text: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn leaf(kind: NodeKind, text: &str) -> Self {
+        Node {
+            kind,
+            text: text.to_string(),
+            children: Vec::new(),
+        }
+    }
+
+    fn branch(kind: NodeKind, children: Vec<Node>) -> Self {
+        Node {
+            kind,
+            text: String::new(),
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// The source text of a leaf, empty for other nodes.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Whether the reader skips this node: whitespace, comments and discards.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind,
+            NodeKind::Whitespace | NodeKind::Comment | NodeKind::Discard
+        )
+    }
+
+    /// Whether this node is a form the reader returns a value for.
+    pub fn is_form(&self) -> bool {
+        !self.is_trivia() && self.kind != NodeKind::Delimiter
+    }
+
+    /// The forms among the children. For a namespace map these are its keys and values.
+    pub fn forms(&self) -> impl Iterator<Item = &Node> {
+        self.entries().into_iter().map(|i| &self.children[i])
+    }
+
+    /// Reads the node's source into a value.
+    ///
+    /// Keys of a namespace map get their namespace from the map, so reading such a key node
+    /// on its own gives the unqualified key.
+    pub fn to_edn(&self) -> Result<Edn, EdnError> {
+        self.to_edn_with_options(&ReaderOptions::default())
+    }
+
+    pub fn to_edn_with_options(&self, options: &ReaderOptions) -> Result<Edn, EdnError> {
+        read_str_with_options(&self.to_string(), options.clone())
+    }
+
+    /// Indices of the children that are elements of a collection, or entries of a map.
+    fn entries(&self) -> Vec<usize> {
+        // The namespace of a namespace map is a form too, but not an entry.
+        let skip = match self.kind {
+            NodeKind::NamespaceMap => self
+                .children
+                .iter()
+                .position(|child| child.kind == NodeKind::Delimiter && child.text == "{")
+                .map_or(0, |i| i + 1),
+            _ => 0,
+        };
+        (skip..self.children.len())
+            .filter(|&i| self.children[i].is_form())
+            .collect()
+    }
+
+    /// The form that metadata applies to, or the node itself.
+    fn target(&self) -> &Node {
+        match self.kind {
+            NodeKind::Meta => self
+                .children
+                .iter()
+                .rfind(|child| child.is_form())
+                .map_or(self, Node::target),
+            _ => self,
+        }
+    }
+
+    fn target_mut(&mut self) -> &mut Node {
+        if self.kind != NodeKind::Meta {
+            return self;
+        }
+        match self.children.iter().rposition(Node::is_form) {
+            Some(i) => self.children[i].target_mut(),
+            None => self,
+        }
+    }
+
+    /// Index of the child holding the value at `key`, if this node is a map, vector or list.
+    fn find(&self, key: &Edn, options: &ReaderOptions) -> Result<Option<usize>, ()> {
+        let entries = self.entries();
+        match self.kind {
+            NodeKind::Map | NodeKind::NamespaceMap => {
+                let namespace = self.namespace();
+                for pair in entries.chunks(2) {
+                    let [k, v] = pair else { break };
+                    let k = self.children[*k]
+                        .to_edn_with_options(options)
+                        .map_err(|_| ())?;
+                    let k = match namespace {
+                        Some(namespace) => qualify_key(namespace, k),
+                        None => k,
+                    };
+                    if k == *key {
+                        return Ok(Some(*v));
+                    }
+                }
+                Ok(None)
+            }
+            NodeKind::Vector | NodeKind::List => match key {
+                Edn::Int(i) => Ok(usize::try_from(*i)
+                    .ok()
+                    .and_then(|i| entries.get(i).copied())),
+                _ => Ok(None),
+            },
+            _ => Err(()),
+        }
+    }
+
+    fn namespace(&self) -> Option<&str> {
+        match self.kind {
+            NodeKind::NamespaceMap => self
+                .children
+                .iter()
+                .find(|child| child.is_form())
+                .map(|ns| ns.text.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Adds `key value` after the last entry of a map, separated from it like the last entry is
+    /// from the one before, or by a space.
+    fn insert(&mut self, key: Node, value: Node) {
+        let entries = self.entries();
+        let (at, separator) = match entries.as_slice() {
+            [] => {
+                let open = self
+                    .children
+                    .iter()
+                    .position(|child| {
+                        child.kind == NodeKind::Delimiter && child.text.ends_with('{')
+                    })
+                    .unwrap_or(0);
+                (open + 1, None)
+            }
+            [.., last_key, last_value] => {
+                let before_key = &self.children[*last_key - 1];
+                let separator = match before_key.kind {
+                    NodeKind::Whitespace if entries.len() > 2 => before_key.clone(),
+                    _ => Node::leaf(NodeKind::Whitespace, " "),
+                };
+                (*last_value + 1, Some(separator))
+            }
+            [..] => unreachable!("maps have an even number of forms"),
+        };
+        let nodes =
+            separator
+                .into_iter()
+                .chain([key, Node::leaf(NodeKind::Whitespace, " "), value]);
+        self.children.splice(at..at, nodes);
+    }
+}
+
+/// The source of the node, byte for byte.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)?;
+        for child in &self.children {
+            write!(f, "{child}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// The document has no form to edit.
+    EmptyDocument,
+    /// Nothing is at this path, and it cannot be added since the parent is not a map.
+    NotFound(Vec<Edn>),
+    /// The value at this path is not a map, vector or list to look keys up in.
+    NotAssociative(Vec<Edn>),
+    /// The value does not print as text that reads back as it, like an element tagged `1x`.
+    InvalidValue(Edn),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::EmptyDocument => write!(f, "Document has no forms"),
+            EditError::NotFound(path) => write!(f, "No value at {}", Edn::Vec(path.clone())),
+            EditError::NotAssociative(path) => write!(
+                f,
+                "Value at {} is not a map, vector or list",
+                Edn::Vec(path.clone())
+            ),
+            // Printed with `Debug`, since `Display` fails on such values.
+            EditError::InvalidValue(edn) => write!(f, "Value does not print as EDN: {edn:?}"),
+        }
+    }
+}
+
+impl Error for EditError {}
+
+/// The syntax tree of a whole source text: its top level forms and the trivia around them.
+#[derive(Debug, Clone)]
+pub struct Document {
+    nodes: Vec<Node>,
+    options: ReaderOptions,
+}
+
+impl Document {
+    /// Parses `source`, failing with the reader's error if it is not valid EDN.
+    pub fn parse(source: &str) -> Result<Self, EdnError> {
+        Self::parse_with_options(source, ReaderOptions::default())
+    }
+
+    pub fn parse_with_options(source: &str, options: ReaderOptions) -> Result<Self, EdnError> {
+        // The reader checks everything the tree is built on, like balanced delimiters.
+        Reader::new(source)
+            .with_options(options.clone())
+            .collect::<Result<Vec<_>, _>>()?;
+        let nodes = Parser::new(source).parse_all()?;
+        Ok(Document { nodes, options })
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Reads the top level forms, like [`crate::edn_reader::read_all`] on the source.
+    pub fn to_edn(&self) -> Result<Vec<Edn>, EdnError> {
+        let source = self.to_string();
+        Reader::new(&source)
+            .with_options(self.options.clone())
+            .collect()
+    }
+
+    /// The node of the value at `path` in the first form, like `get-in`. Map keys are compared
+    /// as values, vectors and lists are indexed with `Edn::Int`.
+    pub fn get_in(&self, path: &[Edn]) -> Option<&Node> {
+        let mut node = self.nodes.iter().find(|node| node.is_form())?;
+        for key in path {
+            let target = node.target();
+            let i = target.find(key, &self.options).ok()??;
+            node = &target.children[i];
+        }
+        Some(node)
+    }
+
+    /// Replaces the value at `path` in the first form with `value`, like `assoc-in`.
+    ///
+    /// A missing last key of a map is added after the map's last entry. Only the replaced or
+    /// added nodes change, comments and whitespace everywhere else stay as they are.
+    pub fn set_in(&mut self, path: &[Edn], value: &Edn) -> Result<(), EditError> {
+        let mut node = self
+            .nodes
+            .iter_mut()
+            .find(|node| node.is_form())
+            .ok_or(EditError::EmptyDocument)?;
+        for (depth, key) in path.iter().enumerate() {
+            let target = node.target_mut();
+            let here = || path[..depth].to_vec();
+            let found = target
+                .find(key, &self.options)
+                .map_err(|()| EditError::NotAssociative(here()))?;
+            match found {
+                Some(i) => node = &mut target.children[i],
+                None if depth + 1 == path.len()
+                    && matches!(target.kind, NodeKind::Map | NodeKind::NamespaceMap) =>
+                {
+                    let key = key_node(key, target.namespace())?;
+                    target.insert(key, form_node(value)?);
+                    return Ok(());
+                }
+                None => return Err(EditError::NotFound(path[..=depth].to_vec())),
+            }
+        }
+        *node = form_node(value)?;
+        Ok(())
+    }
+}
+
+/// The source of the document, byte for byte.
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            write!(f, "{node}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The node of a printed value, including its metadata, checking that it reads back as the
+/// value.
+fn form_node(edn: &Edn) -> Result<Node, EditError> {
+    let invalid = || EditError::InvalidValue(edn.clone());
+    let source =
+        edn_writer::to_string(edn, &PrintOptions::new().print_meta(true)).map_err(|_| invalid())?;
+    let read = read_str_with_options(&source, ReaderOptions::new().keep_unknown_tags());
+    if read.as_ref() != Ok(edn) {
+        return Err(invalid());
+    }
+    parse_node(&source).ok_or_else(invalid)
+}
+
+/// The node of a key added to a map, which is `#:namespace{}` map if `namespace` is set.
+/// Keys in that namespace are written without it, and keys without a namespace as `:_/name`.
+fn key_node(key: &Edn, namespace: Option<&str>) -> Result<Node, EditError> {
+    let Some(namespace) = namespace else {
+        return form_node(key);
+    };
+    let (prefix, key_namespace, name) = match key {
+        Edn::Keyword(k) => (":", k.namespace(), k.name()),
+        // `x/nil` without its namespace would read as `nil`.
+        Edn::Symbol(s) if !matches!(s.name(), "nil" | "true" | "false") => {
+            ("", s.namespace(), s.name())
+        }
+        _ => return form_node(key),
+    };
+    let source = match key_namespace {
+        None => format!("{prefix}_/{name}"),
+        Some(ns) if ns == namespace && !name.contains('/') => format!("{prefix}{name}"),
+        Some(_) => return form_node(key),
+    };
+    parse_node(&source).ok_or_else(|| EditError::InvalidValue(key.clone()))
+}
+
+/// The node of `source` if it is exactly one form.
+fn parse_node(source: &str) -> Option<Node> {
+    let mut parser = Parser::new(source);
+    let node = parser.parse_form().ok()?;
+    (parser.offset == source.len()).then_some(node)
+}
+
+fn is_whitespace(ch: char) -> bool {
+    ch.is_whitespace() || ch == ','
+}
+
+/// Chars that end a token, like the reader's terminating macros.
+fn is_terminator(ch: char) -> bool {
+    is_whitespace(ch)
+        || matches!(
+            ch,
+            '"' | ';' | '^' | '(' | ')' | '[' | ']' | '{' | '}' | '\\'
+        )
+}
+
+/// Builds the tree from source the reader has accepted, so it only checks what it needs to
+/// find the end of each node.
+struct Parser<'s> {
+    source: &'s str,
+    offset: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn new(source: &'s str) -> Self {
+        Parser { source, offset: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.source[self.offset..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Result<char, EdnError> {
+        let ch = self
+            .peek()
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedEof))?;
+        self.offset += ch.len_utf8();
+        Ok(ch)
+    }
+
+    fn bump_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.offset += self.peek().unwrap().len_utf8();
+        }
+    }
+
+    fn error(&self, kind: ErrorKind) -> EdnError {
+        let mut position = Position::default();
+        for ch in self.source[..self.offset].chars() {
+            position.advance(ch);
+        }
+        EdnError::new(kind, position)
+    }
+
+    /// A leaf with the text from `start` to the current offset.
+    fn leaf(&self, kind: NodeKind, start: usize) -> Node {
+        Node::leaf(kind, &self.source[start..self.offset])
+    }
+
+    fn parse_all(&mut self) -> Result<Vec<Node>, EdnError> {
+        let mut nodes = Vec::new();
+        loop {
+            self.parse_trivia(&mut nodes)?;
+            if self.peek().is_none() {
+                return Ok(nodes);
+            }
+            nodes.push(self.parse_form()?);
+        }
+    }
+
+    /// Adds the whitespace, comments and discards before the next form to `nodes`.
+    fn parse_trivia(&mut self, nodes: &mut Vec<Node>) -> Result<(), EdnError> {
+        loop {
+            let start = self.offset;
+            match self.peek() {
+                Some(ch) if is_whitespace(ch) => {
+                    self.bump_while(is_whitespace);
+                    nodes.push(self.leaf(NodeKind::Whitespace, start));
+                }
+                Some(';') => {
+                    self.bump_while(|ch| ch != '\n' && ch != '\r');
+                    nodes.push(self.leaf(NodeKind::Comment, start));
+                }
+                Some('#') if self.peek_second() == Some('_') => {
+                    self.offset += 2;
+                    let mut children = vec![self.leaf(NodeKind::Delimiter, start)];
+                    self.parse_trivia(&mut children)?;
+                    children.push(self.parse_form()?);
+                    nodes.push(Node::branch(NodeKind::Discard, children));
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn parse_form(&mut self) -> Result<Node, EdnError> {
+        let start = self.offset;
+        match self.bump()? {
+            '(' => self.parse_collection(NodeKind::List, start, ')'),
+            '[' => self.parse_collection(NodeKind::Vector, start, ']'),
+            '{' => self.parse_collection(NodeKind::Map, start, '}'),
+            '"' => {
+                loop {
+                    match self.bump()? {
+                        '"' => break,
+                        '\\' => {
+                            self.bump()?;
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(self.leaf(NodeKind::Token, start))
+            }
+            '\\' => {
+                self.bump()?;
+                self.bump_while(|ch| !is_terminator(ch));
+                Ok(self.leaf(NodeKind::Token, start))
+            }
+            '^' => self.parse_meta(start),
+            '#' => match self.bump()? {
+                '{' => self.parse_collection(NodeKind::Set, start, '}'),
+                '^' => self.parse_meta(start),
+                '#' => self.parse_prefixed(NodeKind::SymbolicValue, start),
+                ':' => {
+                    let mut children = vec![self.leaf(NodeKind::Delimiter, start)];
+                    self.parse_trivia(&mut children)?;
+                    children.push(self.parse_form()?);
+                    self.parse_trivia(&mut children)?;
+                    let open = self.offset;
+                    if self.bump()? != '{' {
+                        return Err(self.error(ErrorKind::NamespacedMapMissingMap));
+                    }
+                    children.push(self.leaf(NodeKind::Delimiter, open));
+                    self.parse_children(&mut children, '}')?;
+                    Ok(Node::branch(NodeKind::NamespaceMap, children))
+                }
+                ch if ch.is_alphabetic() => {
+                    self.bump_while(|ch| !is_terminator(ch));
+                    self.parse_prefixed(NodeKind::Tagged, start)
+                }
+                ch => Err(self.error(ErrorKind::NoDispatchMacro(ch))),
+            },
+            ch @ (')' | ']' | '}') => Err(self.error(ErrorKind::UnmatchedDelimiter(ch))),
+            ch => {
+                // Numbers end at any macro char, other tokens only at terminating ones.
+                let number = ch.is_ascii_digit()
+                    || matches!(ch, '+' | '-') && self.peek().is_some_and(|ch| ch.is_ascii_digit());
+                if number {
+                    self.bump_while(|ch| !is_terminator(ch) && ch != '#');
+                } else {
+                    self.bump_while(|ch| !is_terminator(ch));
+                }
+                Ok(self.leaf(NodeKind::Token, start))
+            }
+        }
+    }
+
+    /// Parses the form after a prefix that ends at the current offset.
+    fn parse_prefixed(&mut self, kind: NodeKind, start: usize) -> Result<Node, EdnError> {
+        let mut children = vec![self.leaf(NodeKind::Delimiter, start)];
+        self.parse_trivia(&mut children)?;
+        children.push(self.parse_form()?);
+        Ok(Node::branch(kind, children))
+    }
+
+    fn parse_meta(&mut self, start: usize) -> Result<Node, EdnError> {
+        let mut node = self.parse_prefixed(NodeKind::Meta, start)?;
+        self.parse_trivia(&mut node.children)?;
+        node.children.push(self.parse_form()?);
+        Ok(node)
+    }
+
+    fn parse_collection(
+        &mut self,
+        kind: NodeKind,
+        start: usize,
+        close: char,
+    ) -> Result<Node, EdnError> {
+        let mut children = vec![self.leaf(NodeKind::Delimiter, start)];
+        self.parse_children(&mut children, close)?;
+        Ok(Node::branch(kind, children))
+    }
+
+    /// Parses the elements of a collection up to and including the `close` delimiter.
+    fn parse_children(&mut self, children: &mut Vec<Node>, close: char) -> Result<(), EdnError> {
+        loop {
+            self.parse_trivia(children)?;
+            match self.peek() {
+                None => return Err(self.error(ErrorKind::UnexpectedEof)),
+                Some(ch) if ch == close => {
+                    let start = self.offset;
+                    self.bump()?;
+                    children.push(self.leaf(NodeKind::Delimiter, start));
+                    return Ok(());
+                }
+                Some(_) => children.push(self.parse_form()?),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::{read_all, read_str};
+
+    const DEPS: &str = r#";; Project dependencies
+{:paths ["src" "resources"]
+
+ :deps {org.clojure/clojure {:mvn/version "1.11.1"}, ; pinned
+        #_#_old/lib {:mvn/version "0.1"}}
+
+ :aliases
+ {:test {:extra-paths ["test"]
+         :extra-deps #:io.github.cognitect-labs{test-runner {:git/tag "v0.5.1"}}}
+  :meta ^{:doc "x"} [1 2.0M \a ##Inf #inst "2024-01-01"]}}
+"#;
+
+    fn edn(s: &str) -> Edn {
+        read_str(s.to_string()).unwrap()
+    }
+
+    fn path(s: &str) -> Vec<Edn> {
+        read_all(s).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let doc = Document::parse(DEPS).unwrap();
+        assert_eq!(doc.to_string(), DEPS);
+        assert_eq!(doc.to_edn(), read_all(DEPS));
+        assert_eq!(doc.nodes()[0].kind(), NodeKind::Comment);
+        for source in ["", " ,; only a comment", "1 #_ 2 (a b) \"s\\\"\" #{} ^:k x"] {
+            let doc = Document::parse(source).unwrap();
+            assert_eq!(doc.to_string(), source);
+            assert_eq!(doc.to_edn(), read_all(source));
+        }
+        assert_eq!(
+            Document::parse("{:a").unwrap_err(),
+            read_all("{:a").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_get_in() {
+        let doc = Document::parse(DEPS).unwrap();
+        let node = doc.get_in(&path(":aliases :test :extra-paths")).unwrap();
+        assert_eq!(node.to_string(), r#"["test"]"#);
+        let node = doc
+            .get_in(&path(
+                ":aliases :test :extra-deps io.github.cognitect-labs/test-runner",
+            ))
+            .unwrap();
+        assert_eq!(node.to_edn(), Ok(edn(r#"{:git/tag "v0.5.1"}"#)));
+        assert_eq!(
+            doc.get_in(&path(":aliases :meta 1")).unwrap().text(),
+            "2.0M"
+        );
+        assert!(doc.get_in(&path(":deps old/lib")).is_none());
+        assert!(doc.get_in(&path(":paths 2")).is_none());
+    }
+
+    #[test]
+    fn test_set_in() {
+        let mut doc = Document::parse(DEPS).unwrap();
+        doc.set_in(
+            &path(":aliases :test :extra-paths"),
+            &edn(r#"["test" "dev"]"#),
+        )
+        .unwrap();
+        doc.set_in(&path(":paths 0"), &edn(r#""source""#)).unwrap();
+        doc.set_in(
+            &path(":aliases :test :main-opts"),
+            &edn(r#"["-m" "runner"]"#),
+        )
+        .unwrap();
+        doc.set_in(&path(":deps foo/bar"), &edn(r#"{:local/root "."}"#))
+            .unwrap();
+        let expected = DEPS
+            .replace(r#"["test"]"#, r#"["test" "dev"]"#)
+            .replace(r#""src""#, r#""source""#)
+            .replace(
+                r#"{test-runner {:git/tag "v0.5.1"}}}"#,
+                "{test-runner {:git/tag \"v0.5.1\"}}\n         :main-opts [\"-m\" \"runner\"]}",
+            )
+            .replace(
+                "{:mvn/version \"1.11.1\"},",
+                "{:mvn/version \"1.11.1\"} foo/bar {:local/root \".\"},",
+            );
+        assert_eq!(doc.to_string(), expected);
+        assert_eq!(doc.to_edn(), read_all(&expected));
+
+        let mut doc = Document::parse("[{}]").unwrap();
+        doc.set_in(&path("0 :a"), &edn("1")).unwrap();
+        doc.set_in(&[], &edn("#{}")).unwrap();
+        assert_eq!(doc.to_string(), "#{}");
+
+        let mut doc = Document::parse("[]").unwrap();
+        doc.set_in(&[], &edn("^:private [1]")).unwrap();
+        assert_eq!(doc.to_edn().unwrap()[0].meta(), edn("^:private [1]").meta());
+    }
+
+    #[test]
+    fn test_set_in_namespace_map() {
+        let mut doc = Document::parse("#:x{:a 1}").unwrap();
+        for (key, value) in [
+            (":b", "2"),
+            (":x/c", "3"),
+            (":y/d", "4"),
+            ("e", "5"),
+            ("x/f", "6"),
+        ] {
+            doc.set_in(&path(key), &edn(value)).unwrap();
+        }
+        assert_eq!(doc.to_string(), "#:x{:a 1 :_/b 2 :c 3 :y/d 4 _/e 5 f 6}");
+        assert_eq!(
+            doc.get_in(&path(":b")).map(Node::to_string).as_deref(),
+            Some("2")
+        );
+        assert_eq!(
+            doc.get_in(&path(":x/c")).map(Node::to_string).as_deref(),
+            Some("3")
+        );
+        assert_eq!(
+            doc.to_edn(),
+            read_all("{:x/a 1 :b 2 :x/c 3 :y/d 4 e 5 x/f 6}")
+        );
+    }
+
+    #[test]
+    fn test_edit_errors() {
+        let mut doc = Document::parse("{:a 1, :b [1]}").unwrap();
+        assert_eq!(
+            doc.set_in(&path(":a :x"), &edn("2")),
+            Err(EditError::NotAssociative(path(":a")))
+        );
+        assert_eq!(
+            doc.set_in(&path(":b 1"), &edn("2")),
+            Err(EditError::NotFound(path(":b 1")))
+        );
+        assert_eq!(
+            doc.set_in(&path(":c :d"), &edn("2")),
+            Err(EditError::NotFound(path(":c")))
+        );
+        assert_eq!(
+            Document::parse(" ").unwrap().set_in(&[], &edn("1")),
+            Err(EditError::EmptyDocument)
+        );
+        for tag in ["", "1", "a b", "inst"] {
+            let value = Edn::TaggedElement(tag.to_string(), Box::new(Edn::Int(1)));
+            for key in [":a", ":c"] {
+                assert_eq!(
+                    doc.set_in(&path(key), &value),
+                    Err(EditError::InvalidValue(value.clone()))
+                );
+            }
+            let value = Edn::Map([(value.clone(), Edn::Nil)].into());
+            assert!(doc.set_in(&path(":a"), &value).is_err());
+        }
+        assert_eq!(doc.to_string(), "{:a 1, :b [1]}");
+    }
+}
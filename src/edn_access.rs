@@ -0,0 +1,241 @@
+//! Accessors for reading parsed values without matching on [`Edn`] by hand.
+//!
+//! Like the rest of the crate they look through metadata, so `^:m [1]` is a vector.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Index;
+
+use num::ToPrimitive;
+
+use crate::edn_reader::Edn;
+use crate::edn_symbol::{Keyword, Symbol};
+
+static NIL: Edn = Edn::Nil;
+
+impl Edn {
+    pub fn is_nil(&self) -> bool {
+        matches!(self.without_meta(), Edn::Nil)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.without_meta() {
+            Edn::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The text of a string. Symbols and keywords have [`Edn::as_symbol`] and
+    /// [`Edn::as_keyword`].
+    pub fn as_str(&self) -> Option<&str> {
+        match self.without_meta() {
+            Edn::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> Option<char> {
+        match self.without_meta() {
+            Edn::Char(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer, including big integers that fit in an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.without_meta() {
+            Edn::Int(n) => Some(*n),
+            Edn::BigInt(n) => n.to_i64(),
+            _ => None,
+        }
+    }
+
+    /// Any number as the closest `f64`, like `serde_json::Value::as_f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.without_meta() {
+            Edn::Int(n) => Some(*n as f64),
+            Edn::BigInt(n) => n.to_f64(),
+            Edn::Float(n) => Some(n.into_inner()),
+            Edn::BigDecimal(n) => n.to_f64(),
+            Edn::BigRational(n) => n.to_f64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_symbol(&self) -> Option<&Symbol> {
+        match self.without_meta() {
+            Edn::Symbol(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_keyword(&self) -> Option<&Keyword> {
+        match self.without_meta() {
+            Edn::Keyword(k) => Some(k),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&Vec<Edn>> {
+        match self.without_meta() {
+            Edn::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec(&self) -> Option<&Vec<Edn>> {
+        match self.without_meta() {
+            Edn::Vec(vec) => Some(vec),
+            _ => None,
+        }
+    }
+
+    pub fn as_set(&self) -> Option<&BTreeSet<Edn>> {
+        match self.without_meta() {
+            Edn::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&BTreeMap<Edn, Edn>> {
+        match self.without_meta() {
+            Edn::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` like Clojure's `get`: the value of a map entry, the element of a
+    /// vector or list at an integer index, or the element of a set equal to `key`.
+    pub fn get(&self, key: &Edn) -> Option<&Edn> {
+        match self.without_meta() {
+            Edn::Map(map) => map.get(key),
+            Edn::Vec(vec) | Edn::List(vec) => {
+                let i = usize::try_from(key.as_i64()?).ok()?;
+                vec.get(i)
+            }
+            Edn::Set(set) => set.get(key),
+            _ => None,
+        }
+    }
+
+    /// Looks up the keyword `name`, written with or without its leading `:`.
+    ///
+    /// ```
+    /// # use edn_rs::edn_reader::read_str;
+    /// let edn = read_str("{:user/id 7}".to_string()).unwrap();
+    /// assert_eq!(edn.get_kw("user/id").and_then(|id| id.as_i64()), Some(7));
+    /// ```
+    pub fn get_kw(&self, name: &str) -> Option<&Edn> {
+        self.get(&Edn::Keyword(name.parse().ok()?))
+    }
+
+    /// Follows `path` with [`Edn::get`], returning `None` as soon as a key is missing.
+    pub fn get_in(&self, path: &[Edn]) -> Option<&Edn> {
+        path.iter().try_fold(self, |edn, key| edn.get(key))
+    }
+}
+
+/// The element at an index of a vector or list, `nil` when there is none.
+impl Index<usize> for Edn {
+    type Output = Edn;
+
+    fn index(&self, i: usize) -> &Edn {
+        match self.without_meta() {
+            Edn::Vec(vec) | Edn::List(vec) => vec.get(i).unwrap_or(&NIL),
+            _ => &NIL,
+        }
+    }
+}
+
+/// The value of a key as returned by [`Edn::get`], `nil` when there is none.
+impl Index<&Edn> for Edn {
+    type Output = Edn;
+
+    fn index(&self, key: &Edn) -> &Edn {
+        self.get(key).unwrap_or(&NIL)
+    }
+}
+
+impl Index<&Keyword> for Edn {
+    type Output = Edn;
+
+    fn index(&self, key: &Keyword) -> &Edn {
+        self.get(&Edn::Keyword(key.clone())).unwrap_or(&NIL)
+    }
+}
+
+/// The value of a keyword key as returned by [`Edn::get_kw`], so that `edn["user/id"]`
+/// reads `:user/id`. String keys need `edn[&Edn::String(..)]`.
+impl Index<&str> for Edn {
+    type Output = Edn;
+
+    fn index(&self, name: &str) -> &Edn {
+        self.get_kw(name).unwrap_or(&NIL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::read_str;
+
+    fn read(s: &str) -> Edn {
+        read_str(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_as() {
+        let edn = read(r#"[nil true "s" \c sym :kw 12 12N 1.5 1.5M 1/2 (1) [2] #{3} {4 5}]"#);
+        let items = edn.as_vec().unwrap();
+        assert!(items[0].is_nil());
+        assert_eq!(items[1].as_bool(), Some(true));
+        assert_eq!(items[2].as_str(), Some("s"));
+        assert_eq!(items[3].as_char(), Some('c'));
+        assert_eq!(items[4].as_symbol().map(Symbol::name), Some("sym"));
+        assert_eq!(items[5].as_keyword().map(Keyword::name), Some("kw"));
+        assert_eq!(items[6].as_i64(), Some(12));
+        assert_eq!(items[7].as_i64(), Some(12));
+        let floats: Vec<_> = items[6..11].iter().map(Edn::as_f64).collect();
+        assert_eq!(floats, [12.0, 12.0, 1.5, 1.5, 0.5].map(Some));
+        assert_eq!(items[11].as_list().map(Vec::len), Some(1));
+        assert_eq!(items[12].as_vec().map(Vec::len), Some(1));
+        assert_eq!(items[13].as_set().map(BTreeSet::len), Some(1));
+        assert_eq!(items[14].as_map().map(BTreeMap::len), Some(1));
+
+        assert_eq!(items[2].as_keyword(), None);
+        assert_eq!(items[5].as_str(), None);
+        assert_eq!(read("99999999999999999999").as_i64(), None);
+        assert_eq!(read("^:m [1]").as_vec(), Some(&vec![Edn::Int(1)]));
+    }
+
+    #[test]
+    fn test_get() {
+        let edn = read(r#"{:user/id 7, "name" "ann", [1 2] :pair, :roles [:admin #{:dev}]}"#);
+        assert_eq!(edn.get_kw("user/id"), Some(&Edn::Int(7)));
+        assert_eq!(edn.get_kw(":user/id"), Some(&Edn::Int(7)));
+        assert_eq!(edn.get_kw("id"), None);
+        assert_eq!(edn.get_kw("not a keyword"), None);
+        assert_eq!(edn.get(&read(r#""name""#)), Some(&read(r#""ann""#)));
+        assert_eq!(edn.get(&read("[1 2]")), Some(&read(":pair")));
+        assert_eq!(
+            edn.get_in(&[read(":roles"), Edn::Int(1), read(":dev")]),
+            Some(&read(":dev"))
+        );
+        assert_eq!(edn.get_in(&[read(":roles"), Edn::Int(2)]), None);
+        assert_eq!(edn.get_in(&[read(":roles"), Edn::Int(-1)]), None);
+        assert_eq!(edn.get_in(&[]), Some(&edn));
+    }
+
+    #[test]
+    fn test_index() {
+        let edn = read(r#"^:m {:users [{:name "ann"}], "k" 1}"#);
+        assert_eq!(edn["users"][0]["name"].as_str(), Some("ann"));
+        assert_eq!(
+            edn[&Keyword::new("users").unwrap()][0],
+            read(r#"{:name "ann"}"#)
+        );
+        assert_eq!(edn[&read(r#""k""#)], Edn::Int(1));
+        assert!(edn["k"].is_nil());
+        assert!(edn["users"][1]["name"].is_nil());
+        assert!(edn[0].is_nil());
+    }
+}
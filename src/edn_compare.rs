@@ -3,10 +3,10 @@ use std::{
     process::{Command, Stdio},
 };
 
-use crate::edn_reader;
+use edn_rs::edn_reader;
 
 pub fn rust_edn(input: &str) -> Result<String, String> {
-    let a = edn_reader::read_str(input.to_string())?;
+    let a = edn_reader::read_str(input.to_string()).map_err(|e| e.kind().to_string())?;
     let b = format!("{a}");
     Ok(b)
 }
@@ -40,6 +40,7 @@ pub fn clojure_edn(input: &str) -> Result<String, String> {
             .read_to_string(&mut err)
             .expect("Failed to read stderr");
     }
+    child.wait().expect("Failed to wait on Clojure Process");
     err = err
         .strip_prefix(
             "Picked up _JAVA_OPTIONS: -Djava.util.prefs.userRoot=/home/user/.config/java\n",
@@ -0,0 +1,451 @@
+//! Conversions between Rust values and [`Edn`] for when serde is more than needed.
+//!
+//! `From` builds the value [`crate::edn_reader::read_str`] reads from the printed Rust value,
+//! so integers become [`Edn::Int`] unless they don't fit in an `i64`. `TryFrom<Edn>` looks
+//! through metadata and accepts the variants that convert without losing information.
+
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::hash::{BuildHasher, Hash};
+
+use bigdecimal::BigDecimal;
+use num::{BigInt, BigRational};
+
+use crate::edn_inst::Inst;
+use crate::edn_reader::Edn;
+use crate::edn_symbol::{Keyword, Symbol};
+use crate::edn_uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The value has another type than the one converted to, which is named first.
+    InvalidType(&'static str, Edn),
+    /// The number does not fit in the integer type named first.
+    OutOfRange(&'static str, Edn),
+    /// Different keys of a map or elements of a set converted to the same value.
+    Duplicate(Edn),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::InvalidType(ty, edn) => write!(f, "Expected {ty}, found {edn}"),
+            ConversionError::OutOfRange(ty, edn) => write!(f, "{edn} does not fit in {ty}"),
+            ConversionError::Duplicate(edn) => write!(f, "{edn} converts to a duplicate"),
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+macro_rules! from_int {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Edn {
+            fn from(n: $ty) -> Edn {
+                match i64::try_from(n) {
+                    Ok(n) => Edn::Int(n),
+                    Err(_) => Edn::BigInt(n.into()),
+                }
+            }
+        }
+
+        impl TryFrom<Edn> for $ty {
+            type Error = ConversionError;
+
+            fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+                let fits = match edn.without_meta() {
+                    Edn::Int(n) => <$ty>::try_from(*n).ok(),
+                    Edn::BigInt(n) => <$ty>::try_from(n).ok(),
+                    _ => return Err(ConversionError::InvalidType("an integer", edn)),
+                };
+                fits.ok_or_else(|| ConversionError::OutOfRange(stringify!($ty), edn))
+            }
+        }
+    )*};
+}
+
+from_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Implements the conversions for a type that is the content of one variant.
+macro_rules! from_variant {
+    ($($ty:ty => $variant:ident, $name:literal;)*) => {$(
+        impl From<$ty> for Edn {
+            fn from(value: $ty) -> Edn {
+                Edn::$variant(value)
+            }
+        }
+
+        impl TryFrom<Edn> for $ty {
+            type Error = ConversionError;
+
+            fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+                match edn {
+                    Edn::WithMeta(_, edn) => Self::try_from(*edn),
+                    Edn::$variant(value) => Ok(value),
+                    edn => Err(ConversionError::InvalidType($name, edn)),
+                }
+            }
+        }
+    )*};
+}
+
+from_variant! {
+    bool => Bool, "a boolean";
+    char => Char, "a char";
+    String => String, "a string";
+    Keyword => Keyword, "a keyword";
+    Symbol => Symbol, "a symbol";
+    Inst => Inst, "an inst";
+    Uuid => Uuid, "a UUID";
+}
+
+impl From<&str> for Edn {
+    fn from(s: &str) -> Edn {
+        Edn::String(s.to_string())
+    }
+}
+
+/// Goes through the shortest decimal form of `n`, which is what it prints as, so `1.1f32`
+/// is `1.1` rather than `1.100000023841858`.
+impl From<f32> for Edn {
+    fn from(n: f32) -> Edn {
+        let n = n.to_string().parse().unwrap_or(f64::from(n));
+        Edn::Float(n.into())
+    }
+}
+
+impl From<f64> for Edn {
+    fn from(n: f64) -> Edn {
+        Edn::Float(n.into())
+    }
+}
+
+/// Floats only, integers and ratios have their own conversions to `f64`.
+impl TryFrom<Edn> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        match edn.without_meta() {
+            Edn::Float(n) => Ok(n.into_inner()),
+            _ => Err(ConversionError::InvalidType("a float", edn)),
+        }
+    }
+}
+
+/// Floats that convert back from the `f32`, so `1.1` converts and `1e300` or
+/// `1.0000000001` are out of range.
+impl TryFrom<Edn> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        let n = match edn.without_meta() {
+            Edn::Float(n) => n.into_inner() as f32,
+            _ => return Err(ConversionError::InvalidType("a float", edn)),
+        };
+        if Edn::from(n) == edn {
+            Ok(n)
+        } else {
+            Err(ConversionError::OutOfRange("f32", edn))
+        }
+    }
+}
+
+/// Keeps the value a big integer, like `1N`.
+impl From<BigInt> for Edn {
+    fn from(n: BigInt) -> Edn {
+        Edn::BigInt(n)
+    }
+}
+
+impl TryFrom<Edn> for BigInt {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        match edn {
+            Edn::WithMeta(_, edn) => Self::try_from(*edn),
+            Edn::Int(n) => Ok(n.into()),
+            Edn::BigInt(n) => Ok(n),
+            edn => Err(ConversionError::InvalidType("an integer", edn)),
+        }
+    }
+}
+
+impl From<BigDecimal> for Edn {
+    fn from(n: BigDecimal) -> Edn {
+        Edn::BigDecimal(n)
+    }
+}
+
+impl TryFrom<Edn> for BigDecimal {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        match edn {
+            Edn::WithMeta(_, edn) => Self::try_from(*edn),
+            Edn::Int(n) => Ok(n.into()),
+            Edn::BigInt(n) => Ok(n.into()),
+            Edn::BigDecimal(n) => Ok(n),
+            edn => Err(ConversionError::InvalidType("a big decimal", edn)),
+        }
+    }
+}
+
+/// Keeps the value a ratio even if it is an integer, like when reading `4/2`.
+impl From<BigRational> for Edn {
+    fn from(n: BigRational) -> Edn {
+        Edn::BigRational(n)
+    }
+}
+
+impl TryFrom<Edn> for BigRational {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        match edn {
+            Edn::WithMeta(_, edn) => Self::try_from(*edn),
+            Edn::Int(n) => Ok(BigInt::from(n).into()),
+            Edn::BigInt(n) => Ok(n.into()),
+            Edn::BigRational(n) => Ok(n),
+            edn => Err(ConversionError::InvalidType("a ratio", edn)),
+        }
+    }
+}
+
+/// `None` is `nil`.
+impl<T: Into<Edn>> From<Option<T>> for Edn {
+    fn from(value: Option<T>) -> Edn {
+        value.map_or(Edn::Nil, Into::into)
+    }
+}
+
+/// `nil` is `None`.
+impl<T: TryFrom<Edn, Error = ConversionError>> TryFrom<Edn> for Option<T> {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        if edn.is_nil() {
+            Ok(None)
+        } else {
+            T::try_from(edn).map(Some)
+        }
+    }
+}
+
+impl<T: Into<Edn>> From<Vec<T>> for Edn {
+    fn from(vec: Vec<T>) -> Edn {
+        Edn::Vec(vec.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Converts the elements of a list or vector.
+impl<T: TryFrom<Edn, Error = ConversionError>> TryFrom<Edn> for Vec<T> {
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        match edn {
+            Edn::WithMeta(_, edn) => Self::try_from(*edn),
+            Edn::List(vec) | Edn::Vec(vec) => vec.into_iter().map(T::try_from).collect(),
+            edn => Err(ConversionError::InvalidType("a list or vector", edn)),
+        }
+    }
+}
+
+impl<T: Into<Edn>> From<BTreeSet<T>> for Edn {
+    fn from(set: BTreeSet<T>) -> Edn {
+        Edn::Set(set.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Edn>, S> From<HashSet<T, S>> for Edn {
+    fn from(set: HashSet<T, S>) -> Edn {
+        Edn::Set(set.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<Edn>, V: Into<Edn>> From<BTreeMap<K, V>> for Edn {
+    fn from(map: BTreeMap<K, V>) -> Edn {
+        Edn::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+impl<K: Into<Edn>, V: Into<Edn>, S> From<HashMap<K, V, S>> for Edn {
+    fn from(map: HashMap<K, V, S>) -> Edn {
+        Edn::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+/// Converts the elements of `edn` if it is a set, failing on elements that convert to the
+/// same value, like `1` and `1N` to `i64`.
+fn set<T, C>(edn: Edn, mut insert: impl FnMut(&mut C, T) -> bool) -> Result<C, ConversionError>
+where
+    T: TryFrom<Edn, Error = ConversionError>,
+    C: Default,
+{
+    let set = match edn {
+        Edn::WithMeta(_, edn) => return set(*edn, insert),
+        Edn::Set(set) => set,
+        edn => return Err(ConversionError::InvalidType("a set", edn)),
+    };
+    let mut converted = C::default();
+    for edn in set {
+        if !insert(&mut converted, T::try_from(edn.clone())?) {
+            return Err(ConversionError::Duplicate(edn));
+        }
+    }
+    Ok(converted)
+}
+
+/// Converts the entries of `edn` if it is a map, failing on keys that convert to the same
+/// value.
+fn map<K, V, C>(
+    edn: Edn,
+    mut insert: impl FnMut(&mut C, K, V) -> bool,
+) -> Result<C, ConversionError>
+where
+    K: TryFrom<Edn, Error = ConversionError>,
+    V: TryFrom<Edn, Error = ConversionError>,
+    C: Default,
+{
+    let map = match edn {
+        Edn::WithMeta(_, edn) => return map(*edn, insert),
+        Edn::Map(map) => map,
+        edn => return Err(ConversionError::InvalidType("a map", edn)),
+    };
+    let mut converted = C::default();
+    for (key, value) in map {
+        if !insert(
+            &mut converted,
+            K::try_from(key.clone())?,
+            V::try_from(value)?,
+        ) {
+            return Err(ConversionError::Duplicate(key));
+        }
+    }
+    Ok(converted)
+}
+
+impl<T> TryFrom<Edn> for BTreeSet<T>
+where
+    T: TryFrom<Edn, Error = ConversionError> + Ord,
+{
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        set(edn, BTreeSet::insert)
+    }
+}
+
+impl<T, S> TryFrom<Edn> for HashSet<T, S>
+where
+    T: TryFrom<Edn, Error = ConversionError> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        set(edn, HashSet::insert)
+    }
+}
+
+impl<K, V> TryFrom<Edn> for BTreeMap<K, V>
+where
+    K: TryFrom<Edn, Error = ConversionError> + Ord,
+    V: TryFrom<Edn, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        map(edn, |map: &mut Self, k, v| map.insert(k, v).is_none())
+    }
+}
+
+impl<K, V, S> TryFrom<Edn> for HashMap<K, V, S>
+where
+    K: TryFrom<Edn, Error = ConversionError> + Eq + Hash,
+    V: TryFrom<Edn, Error = ConversionError>,
+    S: BuildHasher + Default,
+{
+    type Error = ConversionError;
+
+    fn try_from(edn: Edn) -> Result<Self, ConversionError> {
+        map(edn, |map: &mut Self, k, v| map.insert(k, v).is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::read_str;
+
+    fn read(s: &str) -> Edn {
+        read_str(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_from() {
+        assert_eq!(Edn::from(-3i8), read("-3"));
+        assert_eq!(Edn::from(u64::MAX), read("18446744073709551615"));
+        assert!(matches!(Edn::from(u64::MAX), Edn::BigInt(_)));
+        assert_eq!(Edn::from(1.5f32), read("1.5"));
+        assert_eq!(Edn::from(1.1f32), read("1.1"));
+        assert_eq!(Edn::from(f32::INFINITY), read("##Inf"));
+        assert_eq!(Edn::from("s"), read(r#""s""#));
+        assert_eq!(Edn::from(BigRational::new(4.into(), 2.into())), read("4/2"));
+        assert_eq!(Edn::from(None::<i32>), Edn::Nil);
+        assert_eq!(Edn::from(vec![Some('a'), None]), read(r"[\a nil]"));
+        assert_eq!(
+            Edn::from(BTreeMap::from([(
+                Keyword::new("a").unwrap(),
+                HashSet::from([1])
+            )])),
+            read("{:a #{1}}")
+        );
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(u8::try_from(read("255")), Ok(255));
+        assert_eq!(i64::try_from(read("12N")), Ok(12));
+        assert_eq!(
+            u8::try_from(read("256")),
+            Err(ConversionError::OutOfRange("u8", Edn::Int(256)))
+        );
+        assert_eq!(
+            String::try_from(read(":a")).unwrap_err().to_string(),
+            "Expected a string, found :a"
+        );
+        assert_eq!(
+            Vec::<Option<bool>>::try_from(read("^:m (true nil)")),
+            Ok(vec![Some(true), None])
+        );
+        let map: HashMap<String, Vec<i32>> = read(r#"{"a" [1 2]}"#).try_into().unwrap();
+        assert_eq!(map, HashMap::from([("a".to_string(), vec![1, 2])]));
+        assert_eq!(
+            BTreeSet::<i64>::try_from(read("#{1 1N}")),
+            Err(ConversionError::Duplicate(read("1N")))
+        );
+        assert_eq!(
+            BigRational::try_from(read("3")),
+            Ok(BigRational::from_integer(3.into()))
+        );
+        assert!(f64::try_from(read("1")).is_err());
+        assert_eq!(f32::try_from(read("1.1")), Ok(1.1));
+        assert_eq!(
+            f32::try_from(read("1e300")),
+            Err(ConversionError::OutOfRange("f32", read("1e300")))
+        );
+        assert!(f32::try_from(read("1.0000000001")).is_err());
+        assert!(f32::try_from(read("##NaN")).unwrap().is_nan());
+        assert_eq!(BigDecimal::try_from(read("3")), Ok(BigDecimal::from(3)));
+        assert_eq!(
+            BigDecimal::try_from(read("30000000000000000000N")),
+            Ok("30000000000000000000".parse().unwrap())
+        );
+        assert_eq!(
+            BigDecimal::try_from(read("1.5M")),
+            Ok("1.5".parse().unwrap())
+        );
+        assert!(BigDecimal::try_from(read("1.5")).is_err());
+    }
+}
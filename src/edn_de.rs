@@ -0,0 +1,598 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, BufReader};
+
+use num::BigInt;
+use num::ToPrimitive;
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Unexpected, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::edn_error::{ErrorKind, SerdeError};
+use crate::edn_reader::{Edn, Reader, ReaderOptions};
+use crate::edn_ser::{from_private, EDN_TOKEN, PRIVATE_PREFIX};
+
+/// Deserializes a `T` from the single EDN form in `s`.
+///
+/// Maps with keyword keys become structs, `nil` becomes `None` and tagged elements become
+/// externally tagged enum variants, so unknown tags are accepted.
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T, SerdeError> {
+    from_forms(Reader::new(s))
+}
+
+/// Like [`from_str`], reading the EDN text from `reader`.
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(reader: R) -> Result<T, SerdeError> {
+    from_forms(Reader::from_reader(BufReader::new(reader)))
+}
+
+pub fn from_edn<T: DeserializeOwned>(edn: Edn) -> Result<T, SerdeError> {
+    T::deserialize(edn)
+}
+
+fn from_forms<T: DeserializeOwned>(forms: Reader) -> Result<T, SerdeError> {
+    let mut forms = forms.with_options(ReaderOptions::new().keep_unknown_tags());
+    let edn = forms
+        .next()
+        .unwrap_or_else(|| Err(ErrorKind::UnexpectedEof.into()))?;
+    if let Some(next) = forms.next() {
+        next?;
+        return Err(de::Error::custom("Trailing forms after the value"));
+    }
+    from_edn(edn)
+}
+
+fn unexpected(edn: &Edn) -> Unexpected<'_> {
+    match edn {
+        Edn::Nil => Unexpected::Unit,
+        Edn::Bool(b) => Unexpected::Bool(*b),
+        Edn::String(s) => Unexpected::Str(s),
+        Edn::Char(c) => Unexpected::Char(*c),
+        Edn::Symbol(_) => Unexpected::Other("symbol"),
+        Edn::Keyword(_) => Unexpected::Other("keyword"),
+        Edn::Int(n) => Unexpected::Signed(*n),
+        Edn::Float(n) => Unexpected::Float(n.into_inner()),
+        Edn::BigInt(_) | Edn::BigDecimal(_) | Edn::BigRational(_) => {
+            Unexpected::Other("big number")
+        }
+        Edn::List(_) | Edn::Vec(_) | Edn::Set(_) => Unexpected::Seq,
+        Edn::Map(_) => Unexpected::Map,
+        Edn::Inst(_) => Unexpected::Other("inst"),
+        Edn::Uuid(_) => Unexpected::Other("uuid"),
+        Edn::TaggedElement(_, _) => Unexpected::Other("tagged element"),
+        Edn::WithMeta(_, edn) => unexpected(edn),
+    }
+}
+
+fn visit_seq<'de, I, V>(iter: I, visitor: V) -> Result<V::Value, SerdeError>
+where
+    I: ExactSizeIterator<Item = Edn>,
+    V: Visitor<'de>,
+{
+    let len = iter.len();
+    let mut seq = SeqAccess { iter, index: 0 };
+    let value = visitor.visit_seq(&mut seq)?;
+    if seq.iter.len() > 0 {
+        return Err(de::Error::invalid_length(len, &"fewer elements"));
+    }
+    Ok(value)
+}
+
+fn visit_map<'de, I, V>(iter: I, visitor: V) -> Result<V::Value, SerdeError>
+where
+    I: ExactSizeIterator<Item = (Edn, Edn)>,
+    V: Visitor<'de>,
+{
+    let len = iter.len();
+    let mut map = MapAccess { iter, value: None };
+    let value = visitor.visit_map(&mut map)?;
+    if map.iter.len() > 0 {
+        return Err(de::Error::invalid_length(len, &"fewer entries"));
+    }
+    Ok(value)
+}
+
+/// Implements `deserialize_*` methods with [`Edn::deserialize_non_str`], ignoring the
+/// lengths and names of tuples and structs like `forward_to_deserialize_any!`.
+macro_rules! forward_to_deserialize_non_str {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {$(
+        fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, SerdeError> {
+            self.deserialize_non_str(visitor)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Edn {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            Edn::Nil => visitor.visit_unit(),
+            Edn::Bool(b) => visitor.visit_bool(b),
+            Edn::String(s) => visitor.visit_string(s),
+            Edn::Symbol(s) => visitor.visit_str(s.as_str()),
+            Edn::Keyword(s) => visitor.visit_str(s.as_str()),
+            Edn::Char(c) => visitor.visit_char(c),
+            Edn::Int(n) => visitor.visit_i64(n),
+            Edn::BigInt(n) => match (n.to_u64(), n.to_i128(), n.to_u128()) {
+                (Some(n), _, _) => visitor.visit_u64(n),
+                (_, Some(n), _) => visitor.visit_i128(n),
+                (_, _, Some(n)) => visitor.visit_u128(n),
+                _ => visitor.visit_string(n.to_string()),
+            },
+            Edn::Float(n) => visitor.visit_f64(n.into_inner()),
+            Edn::BigDecimal(n) => visitor.visit_f64(n.to_f64().unwrap_or(f64::NAN)),
+            Edn::BigRational(n) => visitor.visit_f64(n.to_f64().unwrap_or(f64::NAN)),
+            Edn::List(vec) | Edn::Vec(vec) => visit_seq(vec.into_iter(), visitor),
+            Edn::Set(set) => visit_seq(set.into_iter(), visitor),
+            Edn::Map(map) => visit_map(map.into_iter(), visitor),
+            Edn::Inst(inst) => visitor.visit_string(inst.to_string()),
+            Edn::Uuid(uuid) => visitor.visit_string(uuid.to_string()),
+            Edn::TaggedElement(tag, edn) => visitor.visit_enum(EnumAccess {
+                variant: tag,
+                value: Some(*edn),
+            }),
+            Edn::WithMeta(_, edn) => edn.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.without_meta() {
+            Edn::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.without_meta() {
+            Edn::Nil => visitor.visit_unit(),
+            edn => Err(de::Error::invalid_type(unexpected(edn), &visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if name == EDN_TOKEN {
+            return self.deserialize_private(visitor);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            Edn::String(s) => visitor.visit_byte_buf(s.into_bytes()),
+            edn => edn.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Accepts a keyword, symbol or string for a unit variant, a tagged element or a map with
+    /// a single entry for the other variants.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let (variant, value) = match self {
+            Edn::Keyword(s) => (s.as_str().to_string(), None),
+            Edn::Symbol(s) => (s.as_str().to_string(), None),
+            Edn::String(s) => (s, None),
+            Edn::TaggedElement(tag, edn) => (tag, Some(*edn)),
+            Edn::Map(map) if map.len() == 1 => {
+                let (key, value) = map.into_iter().next().unwrap();
+                match key.without_meta() {
+                    Edn::Keyword(s) => (s.as_str().to_string(), Some(value)),
+                    Edn::Symbol(s) => (s.as_str().to_string(), Some(value)),
+                    Edn::String(s) => (s.clone(), Some(value)),
+                    edn => return Err(de::Error::invalid_type(unexpected(edn), &"variant name")),
+                }
+            }
+            Edn::WithMeta(_, edn) => return edn.deserialize_enum(name, variants, visitor),
+            edn => return Err(de::Error::invalid_type(unexpected(&edn), &visitor)),
+        };
+        visitor.visit_enum(EnumAccess { variant, value })
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_non_str! {
+        deserialize_bool() deserialize_i8() deserialize_i16() deserialize_i32()
+        deserialize_i64() deserialize_i128() deserialize_u8() deserialize_u16()
+        deserialize_u32() deserialize_u64() deserialize_u128() deserialize_f32()
+        deserialize_f64() deserialize_char() deserialize_seq() deserialize_map()
+        deserialize_tuple(_len: usize)
+        deserialize_tuple_struct(_name: &'static str, _len: usize)
+        deserialize_struct(_name: &'static str, _fields: &'static [&'static str])
+    }
+
+    forward_to_deserialize_any! {
+        str string identifier
+    }
+}
+
+impl Edn {
+    /// Like `deserialize_any` for types that are not read from strings, where keywords and
+    /// symbols are a type error naming them rather than the strings `deserialize_any`
+    /// visits them as.
+    fn deserialize_non_str<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.without_meta() {
+            edn @ (Edn::Keyword(_) | Edn::Symbol(_)) => {
+                Err(de::Error::invalid_type(unexpected(edn), &visitor))
+            }
+            _ => de::Deserializer::deserialize_any(self, visitor),
+        }
+    }
+
+    /// Hands the value to `Deserialize for Edn`, passing the variants serde has no type for
+    /// as enum variants named like the newtype structs written by `Serialize for Edn`.
+    fn deserialize_private<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let (name, value) = match self {
+            Edn::Symbol(s) => ("Symbol", Edn::String(s.as_str().to_string())),
+            Edn::Keyword(s) => ("Keyword", Edn::String(s.as_str().to_string())),
+            Edn::BigInt(n) => ("BigInt", Edn::String(n.to_string())),
+            Edn::BigDecimal(n) => ("BigDecimal", Edn::String(n.to_string())),
+            Edn::BigRational(n) => ("BigRational", Edn::String(n.to_string())),
+            Edn::List(vec) => ("List", Edn::Vec(vec)),
+            Edn::Set(set) => ("Set", Edn::Set(set)),
+            Edn::Inst(inst) => ("Inst", Edn::String(inst.to_string())),
+            Edn::Uuid(uuid) => ("Uuid", Edn::String(uuid.to_string())),
+            Edn::WithMeta(meta, edn) => ("Meta", Edn::Vec(vec![Edn::Map(meta), *edn])),
+            edn => return de::Deserializer::deserialize_any(edn, visitor),
+        };
+        visitor.visit_enum(EnumAccess {
+            variant: format!("{PRIVATE_PREFIX}{name}"),
+            value: Some(value),
+        })
+    }
+}
+
+/// Reads any self-describing serde format into an `Edn` value, reversing the mapping of
+/// `Serialize for Edn`. Enum variants from other formats become tagged elements.
+impl<'de> Deserialize<'de> for Edn {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Edn, D::Error> {
+        deserializer.deserialize_newtype_struct(EDN_TOKEN, EdnVisitor)
+    }
+}
+
+struct EdnVisitor;
+
+impl<'de> Visitor<'de> for EdnVisitor {
+    type Value = Edn;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any EDN value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Edn, E> {
+        Ok(Edn::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Edn, E> {
+        Ok(Edn::Int(v))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Edn, E> {
+        Ok(i64::try_from(v).map_or_else(|_| Edn::BigInt(BigInt::from(v)), Edn::Int))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Edn, E> {
+        self.visit_i128(v.into())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Edn, E> {
+        Ok(i64::try_from(v).map_or_else(|_| Edn::BigInt(BigInt::from(v)), Edn::Int))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Edn, E> {
+        Ok(Edn::Float(v.into()))
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Edn, E> {
+        Ok(Edn::Char(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Edn, E> {
+        Ok(Edn::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Edn, E> {
+        Ok(Edn::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Edn, E> {
+        Ok(Edn::Vec(v.iter().map(|&b| Edn::Int(b.into())).collect()))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Edn, E> {
+        Ok(Edn::Nil)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Edn, D::Error> {
+        Edn::deserialize(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Edn, E> {
+        Ok(Edn::Nil)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Edn, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Edn, A::Error> {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(edn) = seq.next_element()? {
+            vec.push(edn);
+        }
+        Ok(Edn::Vec(vec))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Edn, A::Error> {
+        let mut entries = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            entries.insert(key, value);
+        }
+        Ok(Edn::Map(entries))
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Edn, A::Error> {
+        use de::VariantAccess;
+        let (variant, value): (String, _) = data.variant()?;
+        match variant.strip_prefix(PRIVATE_PREFIX) {
+            // Sets are passed as sets, which would come back here as `Edn`.
+            Some("Set") => Ok(Edn::Set(value.newtype_variant::<BTreeSet<Edn>>()?)),
+            Some(_) => from_private(&variant, value.newtype_variant()?).map_err(de::Error::custom),
+            None => {
+                let edn = value.newtype_variant()?;
+                Ok(Edn::TaggedElement(variant, Box::new(edn)))
+            }
+        }
+    }
+}
+
+impl IntoDeserializer<'_, SerdeError> for Edn {
+    type Deserializer = Edn;
+
+    fn into_deserializer(self) -> Edn {
+        self
+    }
+}
+
+struct SeqAccess<I> {
+    iter: I,
+    index: i64,
+}
+
+impl<'de, I: ExactSizeIterator<Item = Edn>> de::SeqAccess<'de> for SeqAccess<I> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        let Some(edn) = self.iter.next() else {
+            return Ok(None);
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(edn)
+            .map(Some)
+            .map_err(|err| err.within(Edn::Int(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapAccess<I> {
+    iter: I,
+    value: Option<(Edn, Edn)>,
+}
+
+impl<'de, I: ExactSizeIterator<Item = (Edn, Edn)>> de::MapAccess<'de> for MapAccess<I> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        let ret = seed
+            .deserialize(key.clone())
+            .map_err(|err| err.within(key.clone()));
+        self.value = Some((key, value));
+        ret.map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| <SerdeError as de::Error>::custom("value is missing"))?;
+        seed.deserialize(value).map_err(|err| err.within(key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: Option<Edn>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = SerdeError;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess), SerdeError> {
+        let variant: de::value::StringDeserializer<SerdeError> = self.variant.into_deserializer();
+        let variant = seed.deserialize(variant)?;
+        Ok((variant, VariantAccess { value: self.value }))
+    }
+}
+
+struct VariantAccess {
+    value: Option<Edn>,
+}
+
+impl VariantAccess {
+    fn value(self, expected: &str) -> Result<Edn, SerdeError> {
+        self.value
+            .ok_or_else(|| de::Error::invalid_type(Unexpected::UnitVariant, &expected))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        match self.value.as_ref().map(Edn::without_meta) {
+            None | Some(Edn::Nil) => Ok(()),
+            Some(edn) => Err(de::Error::invalid_type(unexpected(edn), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self.value("newtype variant")?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self.value("tuple variant")?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self.value("struct variant")?, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap, HashSet};
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Role {
+        Admin,
+        Guest,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Circle(f64),
+        Rect { w: i32, h: i32 },
+        Point(i32, i32),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct User {
+        name: String,
+        #[serde(rename = "user/id")]
+        id: u64,
+        email: Option<String>,
+        roles: BTreeSet<Role>,
+        tags: Vec<String>,
+        shapes: Vec<Shape>,
+    }
+
+    #[test]
+    fn test_from_str() {
+        let user: User = from_str(
+            r#"{:name "Ada" :user/id 7 :email nil :roles #{:admin :guest}
+                :tags ("a" "b") ; comment
+                :shapes [#Circle 1.5 #Rect {:w 1 :h 2} {:Point [3 4]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            user,
+            User {
+                name: "Ada".to_string(),
+                id: 7,
+                email: None,
+                roles: BTreeSet::from([Role::Admin, Role::Guest]),
+                tags: vec!["a".to_string(), "b".to_string()],
+                shapes: vec![
+                    Shape::Circle(1.5),
+                    Shape::Rect { w: 1, h: 2 },
+                    Shape::Point(3, 4)
+                ],
+            }
+        );
+
+        let map: HashMap<String, HashSet<i64>> = from_reader(&b"{:a #{1 2}}"[..]).unwrap();
+        assert_eq!(map["a"], HashSet::from([1, 2]));
+        assert_eq!(from_str::<(char, u8)>("[\\x 255]"), Ok(('x', 255)));
+    }
+
+    #[test]
+    fn test_error_path() {
+        let err = from_str::<Vec<User>>(
+            r#"[{:name "Ada" :user/id 7 :roles #{} :tags [] :shapes []}
+                {:name "Bob" :user/id 8 :roles #{} :tags [] :shapes [#Rect {:w 1 :h :two}]}]"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type: keyword, expected i32 at [1 :shapes 0 :h]"
+        );
+        assert_eq!(
+            from_str::<bool>("yes").unwrap_err().to_string(),
+            "invalid type: symbol, expected a boolean"
+        );
+        assert_eq!(
+            from_str::<Vec<i32>>(":a").unwrap_err().to_string(),
+            "invalid type: keyword, expected a sequence"
+        );
+
+        let err = from_str::<User>("{:name \"Ada\"}").unwrap_err();
+        assert_eq!(err.to_string(), "missing field `user/id`");
+        assert!(from_str::<u8>("256").is_err());
+        assert!(from_str::<u8>("1 2").is_err());
+        assert!(from_str::<u8>("(").is_err());
+    }
+}
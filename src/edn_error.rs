@@ -0,0 +1,217 @@
+use core::fmt;
+use std::error::Error;
+
+use crate::edn_reader::Edn;
+
+/// Location of a character in the source text.
+///
+/// `offset` is a byte offset, `line` and `column` are 1-based and count chars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn advance(&mut self, ch: char) {
+        self.offset += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedEof,
+    UnterminatedString,
+    UnmatchedDelimiter(char),
+    InvalidToken(String),
+    InvalidLeadingCharacter(char),
+    InvalidConstituentCharacter(char),
+    InvalidNumber(String),
+    InvalidCharacter(String),
+    InvalidEscape(String),
+    NoDispatchMacro(char),
+    UnknownTag(String),
+    InvalidTag(Edn),
+    UnknownSymbolicValue(String),
+    InvalidMetadata(Edn),
+    InvalidMetadataTarget(Edn),
+    UnreadableForm,
+    OddMap,
+    DuplicateKey(Edn),
+    InvalidNamespace(String),
+    NamespacedMapMissingMap,
+    NestingTooDeep(usize),
+    Io(String),
+    InvalidTaggedValue(String),
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ErrorKind::*;
+        match self {
+            UnexpectedEof => write!(f, "EOF while reading"),
+            UnterminatedString => write!(f, "EOF while reading string"),
+            UnmatchedDelimiter(ch) => write!(f, "Unmatched delimiter: {ch}"),
+            InvalidToken(s) => write!(f, "Invalid token: {s}"),
+            InvalidLeadingCharacter(ch) => write!(f, "Invalid leading character: {ch}"),
+            InvalidConstituentCharacter(ch) => write!(f, "Invalid constituent character: {ch}"),
+            InvalidNumber(s) => write!(f, "Invalid number: {s}"),
+            InvalidCharacter(s) => write!(f, "Unsupported character: \\{s}"),
+            InvalidEscape(s) => write!(f, "Unsupported escape character: \\{s}"),
+            NoDispatchMacro(ch) => write!(f, "No dispatch macro for: {ch}"),
+            UnknownTag(tag) => write!(f, "No reader function for tag {tag}"),
+            InvalidTag(_) => write!(f, "Reader tag must be a symbol"),
+            UnknownSymbolicValue(s) => write!(f, "Unknown symbolic value: ##{s}"),
+            InvalidMetadata(_) => write!(f, "Metadata must be Symbol,Keyword,String or Map"),
+            InvalidMetadataTarget(_) => write!(f, "Metadata can only be applied to IMetas"),
+            UnreadableForm => write!(f, "Unreadable form"),
+            OddMap => write!(f, "Map literal must contain an even number of forms"),
+            DuplicateKey(key) => write!(f, "Duplicate key: {key}"),
+            InvalidNamespace(s) => {
+                write!(f, "Namespaced map must specify a valid namespace: {s}")
+            }
+            NamespacedMapMissingMap => write!(f, "Namespaced map must specify a map"),
+            NestingTooDeep(depth) => write!(f, "Forms nested deeper than {depth} levels"),
+            Io(msg) => write!(f, "Error while reading input: {msg}"),
+            InvalidTaggedValue(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// An error produced while reading EDN, pointing at the offending form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdnError {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl EdnError {
+    pub fn new(kind: ErrorKind, position: Position) -> Self {
+        EdnError { kind, position }
+    }
+
+    pub(crate) fn with_position(mut self, position: Position) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn offset(&self) -> usize {
+        self.position.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.position.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.position.column
+    }
+}
+
+/// Errors raised outside the reader, e.g. by a tag reader, get their position filled in later.
+impl From<ErrorKind> for EdnError {
+    fn from(kind: ErrorKind) -> Self {
+        EdnError::new(kind, Position::default())
+    }
+}
+
+impl fmt::Display for EdnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.position)
+    }
+}
+
+impl Error for EdnError {}
+
+/// An error from converting between EDN and Rust types with serde.
+///
+/// `path` holds the map keys and sequence indices leading to the value that failed,
+/// outermost first, in the form accepted by `get-in`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerdeError {
+    message: String,
+    path: Vec<Edn>,
+}
+
+impl SerdeError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn path(&self) -> &[Edn] {
+        &self.path
+    }
+
+    /// Records that the error happened inside the value at `key`.
+    pub(crate) fn within(mut self, key: Edn) -> Self {
+        self.path.insert(0, key);
+        self
+    }
+}
+
+impl From<EdnError> for SerdeError {
+    fn from(err: EdnError) -> Self {
+        SerdeError {
+            message: err.to_string(),
+            path: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.path.is_empty() {
+            write!(f, " at {}", Edn::Vec(self.path.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for SerdeError {}
+
+impl serde::de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError {
+            message: msg.to_string(),
+            path: Vec::new(),
+        }
+    }
+}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        <SerdeError as serde::de::Error>::custom(msg)
+    }
+}
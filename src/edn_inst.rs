@@ -0,0 +1,242 @@
+use core::fmt;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    // Same grammar as `clojure.instant/parse-timestamp`.
+    static ref timestampPat: Regex = Regex::new(r"^([0-9][0-9][0-9][0-9])(?:-([0-9][0-9])(?:-([0-9][0-9])(?:[T]([0-9][0-9])(?::([0-9][0-9])(?::([0-9][0-9])(?:[.]([0-9]+))?)?)?)?)?)?(?:[Z]|([-+])([0-9][0-9]):([0-9][0-9]))?$").unwrap();
+}
+
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+/// The instants the reader can read back when printed in UTC, from
+/// `0000-01-01T00:00:00Z` to `9999-12-31T23:59:59.999999999Z`.
+const MIN_SECONDS: i64 = -62_167_219_200;
+const MAX_SECONDS: i64 = 253_402_300_799;
+
+/// A point in time read from an `#inst` literal, stored in UTC with nanosecond precision.
+///
+/// Ordering is chronological. The UTC offset of the literal is applied while reading and is
+/// not kept, like `java.util.Date` in Clojure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Inst {
+    seconds: i64,
+    nanos: u32,
+}
+
+impl Inst {
+    /// Creates an instant `seconds` after the unix epoch plus `nanos`, which must be below one second.
+    ///
+    /// The instant must be in the years 0 to 9999 in UTC, which are the years an `#inst`
+    /// literal can have.
+    pub fn from_timestamp(seconds: i64, nanos: u32) -> Option<Self> {
+        let valid = nanos < 1_000_000_000 && (MIN_SECONDS..=MAX_SECONDS).contains(&seconds);
+        valid.then_some(Inst { seconds, nanos })
+    }
+
+    /// Seconds since the unix epoch.
+    pub fn timestamp(&self) -> i64 {
+        self.seconds
+    }
+
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseInstError(String);
+
+impl fmt::Display for ParseInstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unrecognized date/time syntax: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseInstError {}
+
+impl FromStr for Inst {
+    type Err = ParseInstError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseInstError(s.to_string());
+        let caps = timestampPat.captures(s).ok_or_else(err)?;
+        let field =
+            |i: usize, default: i64| caps.get(i).map_or(default, |m| m.as_str().parse().unwrap());
+        let year = field(1, 0);
+        let month = field(2, 1);
+        let day = field(3, 1);
+        let hour = field(4, 0);
+        let minute = field(5, 0);
+        let second = field(6, 0);
+        let nanos = caps.get(7).map_or(0, |m| {
+            format!("{:0<9}", &m.as_str()[..m.len().min(9)])
+                .parse()
+                .unwrap()
+        });
+        let offset_sign = caps
+            .get(8)
+            .map_or(1, |m| if m.as_str() == "-" { -1 } else { 1 });
+        let offset_hour = field(9, 0);
+        let offset_minute = field(10, 0);
+
+        let valid = (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && (0..=23).contains(&hour)
+            && (0..=59).contains(&minute)
+            && (0..=if minute == 59 { 60 } else { 59 }).contains(&second)
+            && (0..=23).contains(&offset_hour)
+            && (0..=59).contains(&offset_minute);
+        if !valid {
+            return Err(err());
+        }
+
+        let offset = offset_sign * (offset_hour * 3600 + offset_minute * 60);
+        let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY
+            + hour * 3600
+            + minute * 60
+            + second
+            - offset;
+        // Offsets can move the first and last instants out of the years that print as `0000`
+        // to `9999`.
+        Inst::from_timestamp(seconds, nanos).ok_or_else(err)
+    }
+}
+
+/// Prints the timestamp the way Clojure prints instants: in UTC with a `-00:00` offset,
+/// using milliseconds unless that would lose precision.
+impl fmt::Display for Inst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.seconds.div_euclid(SECONDS_PER_DAY);
+        let secs = self.seconds.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )?;
+        if self.nanos.is_multiple_of(NANOS_PER_MILLI) {
+            write!(f, ".{:03}", self.nanos / NANOS_PER_MILLI)?;
+        } else {
+            write!(f, ".{:09}", self.nanos)?;
+        }
+        write!(f, "-00:00")
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between civil dates and days since 1970-01-01, from
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inst(s: &str) -> Inst {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_print() {
+        for (input, output) in [
+            ("1985", "1985-01-01T00:00:00.000-00:00"),
+            ("1985-04-12T23:20:50.52Z", "1985-04-12T23:20:50.520-00:00"),
+            ("1996-12-19T16:39:57-08:00", "1996-12-20T00:39:57.000-00:00"),
+            (
+                "2000-02-29T00:00:00.123456789+01:30",
+                "2000-02-28T22:30:00.123456789-00:00",
+            ),
+            (
+                "1969-12-31T23:59:59.0000000019",
+                "1969-12-31T23:59:59.000000001-00:00",
+            ),
+            ("1990-12-31T23:59:60Z", "1991-01-01T00:00:00.000-00:00"),
+        ] {
+            assert_eq!(inst(input).to_string(), output, "{input}");
+            assert_eq!(inst(output), inst(input));
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        for s in [
+            "banana",
+            "85",
+            "1985-13",
+            "1985-02-29",
+            "1985-04-12T24:00",
+            "1985-04-12T23:20:60Z",
+            "1985-04-12T23:20:50+24:00",
+            "1985-04-12 23:20:50",
+            "١٩٨٥",
+            "0000-01-01T00:00:00+00:01",
+            "9999-12-31T23:59:59-00:01",
+        ] {
+            assert!(s.parse::<Inst>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_timestamp_range() {
+        let first = inst("0000-01-01T00:00:00Z");
+        let last = inst("9999-12-31T23:59:59.999999999Z");
+        assert_eq!(Inst::from_timestamp(first.timestamp(), 0), Some(first));
+        assert_eq!(
+            Inst::from_timestamp(last.timestamp(), 999_999_999),
+            Some(last)
+        );
+        assert_eq!(
+            Inst::from_timestamp(first.timestamp() - 1, 999_999_999),
+            None
+        );
+        assert_eq!(Inst::from_timestamp(last.timestamp() + 1, 0), None);
+        assert_eq!(Inst::from_timestamp(0, 1_000_000_000), None);
+        for inst in [first, last] {
+            assert_eq!(inst.to_string().parse(), Ok(inst));
+        }
+    }
+
+    #[test]
+    fn test_chronological_order() {
+        assert!(inst("1985-04-12T23:20:50Z") < inst("1985-04-12T23:20:50.000000001Z"));
+        assert!(inst("1985-04-12T23:00:00-01:00") > inst("1985-04-12T23:30:00Z"));
+        assert!(inst("1969") < inst("1970"));
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, BufRead};
+
+/// Decodes UTF-8 chars from a `BufRead` one at a time.
+///
+/// Sequences split across buffer boundaries are reassembled. Iteration stops at the first
+/// io error or invalid sequence, which is kept in `error` for the caller to report.
+pub(crate) struct Utf8Chars<R> {
+    reader: R,
+    pub(crate) error: Option<io::Error>,
+}
+
+impl<R: BufRead> Utf8Chars<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Utf8Chars {
+            reader,
+            error: None,
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            let byte = buf.first().copied();
+            if byte.is_some() {
+                self.reader.consume(1);
+            }
+            return Ok(byte);
+        }
+    }
+
+    fn read_char(&mut self) -> io::Result<Option<char>> {
+        let Some(first) = self.read_byte()? else {
+            return Ok(None);
+        };
+        let width = match first {
+            0x00..=0x7F => return Ok(Some(first as char)),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Err(invalid_utf8()),
+        };
+        let mut bytes = [first, 0, 0, 0];
+        for byte in bytes.iter_mut().take(width).skip(1) {
+            *byte = self.read_byte()?.ok_or_else(invalid_utf8)?;
+        }
+        let s = std::str::from_utf8(&bytes[..width]).map_err(|_| invalid_utf8())?;
+        Ok(s.chars().next())
+    }
+}
+
+impl<R: BufRead> Iterator for Utf8Chars<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.read_char() {
+            Ok(ch) => ch,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use super::*;
+
+    /// Hands out one byte per `read` call so every multi-byte char is split across buffers.
+    struct OneByte<'a>(&'a [u8]);
+
+    impl Read for OneByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_sequences() {
+        let s = "aλ€😀z";
+        let chars = Utf8Chars::new(BufReader::with_capacity(1, OneByte(s.as_bytes())));
+        assert_eq!(chars.collect::<String>(), s);
+    }
+
+    #[test]
+    fn test_invalid_sequence() {
+        let mut chars = Utf8Chars::new(&b"a\xE2\x82"[..]);
+        assert_eq!(chars.next(), Some('a'));
+        assert_eq!(chars.next(), None);
+        assert_eq!(
+            chars.error.map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+    }
+}
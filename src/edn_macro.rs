@@ -0,0 +1,479 @@
+//! The [`edn!`](crate::edn) macro, which builds values from EDN written as Rust tokens.
+//!
+//! The items of the module are used by the expanded macro and are not meant to be called
+//! directly.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::edn_reader::{builtin_tag_reader, Edn};
+use crate::edn_symbol::{Keyword, Symbol};
+
+/// Builds an [`Edn`] value from EDN text written as Rust tokens, giving the same value as
+/// [`crate::edn_reader::read_str`] on that text.
+///
+/// ```
+/// use edn_rs::edn;
+/// use edn_rs::edn_reader::read_str;
+///
+/// let port = 8080;
+/// let config = edn!({:port ~port, :hosts #{"a" "b"}, :user/roles [admin ops], :"max-idle" 30});
+/// assert_eq!(
+///     config,
+///     read_str(r#"{:port 8080 :hosts #{"a" "b"} :user/roles [admin ops] :max-idle 30}"#.into())
+///         .unwrap()
+/// );
+/// ```
+///
+/// Since Rust tokens are not EDN, some forms are written differently:
+/// - chars are Rust char literals like `'a'`,
+/// - `~x` or `~(expr)` inserts a value built in Rust, like Clojure's unquote. It is
+///   converted with `Into<Edn>`, which is how big numbers and `##Inf` are written,
+/// - names are idents joined by `.` and `/`. The macro does not see whitespace, so it cannot
+///   tell `:a-b` from `:a -b`, and fails to compile when a name is followed by `-`, `?` or
+///   other punctuation. Keywords like `:first-name` are written as strings, `:"first-name"`,
+///   symbols like `swap!` are inserted with `~`, and `[:a -1]` is written `[:a, -1]`.
+///
+/// ```compile_fail
+/// # use edn_rs::edn;
+/// edn!([:a -b]);
+/// ```
+///
+/// ```compile_fail
+/// # use edn_rs::edn;
+/// edn!({:attr-1 0});
+/// ```
+///
+/// Symbols of punctuation like `+`, `=`, `->`, `&` or `_` are written as is, and so is `*ns*`,
+/// but only names of the form `*name*` can start with `*`. Punctuation at the start of a
+/// form is a symbol on its own, so `(- a b)` has three forms, and `-main` needs `~` too.
+///
+/// Tagged elements use the built-in `#inst` and `#uuid` readers and keep other tags as
+/// [`Edn::TaggedElement`]. Like [`vec!`], invalid values such as maps with duplicate keys
+/// panic when the expression is evaluated.
+///
+/// # Recursion limit
+///
+/// The macro takes a few steps per form, and every step counts against the compiler's
+/// recursion limit of 128. A map of around 40 entries or a vector of around 100 elements is
+/// as much as fits, and larger literals fail with "recursion limit reached while expanding".
+/// Raise the limit in the crate that uses the macro:
+///
+/// ```
+/// #![recursion_limit = "512"]
+/// # use edn_rs::edn;
+/// # fn main() {
+/// # let numbers = edn!([
+/// #     0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29
+/// #     30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57
+/// #     58 59 60 61 62 63 64 65 66 67 68 69 70 71 72 73 74 75 76 77 78 79 80 81 82 83 84 85
+/// #     86 87 88 89 90 91 92 93 94 95 96 97 98 99 100 101 102 103 104 105 106 107 108 109 110
+/// #     111 112 113 114 115 116 117 118 119 120 121 122 123 124 125 126 127 128 129 130 131
+/// #     132 133 134 135 136 137 138 139 140 141 142 143 144 145 146 147 148 149 150 151 152
+/// #     153 154 155 156 157 158 159 160 161 162 163 164 165 166 167 168 169 170 171 172 173
+/// #     174 175 176 177 178 179 180 181 182 183 184 185 186 187 188 189 190 191 192 193 194
+/// #     195 196 197 198 199
+/// # ]);
+/// // `numbers` is `edn!([0 1 2 ... 199])`.
+/// assert_eq!(numbers.as_vec().map(Vec::len), Some(200));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! edn {
+    ($($tt:tt)+) => {
+        $crate::edn_macro::one($crate::__edn_items!([] $($tt)+))
+    };
+}
+
+/// Turns tokens into a `Vec<Item>`, one form at a time.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __edn_items {
+    // Keywords, symbols and tags are idents joined by `.` and `/`. The macro does not see
+    // whitespace, so any other punctuation right after a name could belong to it or start
+    // the next form, and is rejected rather than guessed.
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] . $next:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name $make [$($items,)*] [$($text,)+ ".", stringify!($next)] $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] / $next:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name $make [$($items,)*] [$($text,)+ "/", stringify!($next)] $($rest)*)
+    };
+    // Before the literal arm, which would read `:attr-1` as `:attr -1`.
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] - $($rest:tt)*) => {
+        $crate::__edn_items!(@ambiguous)
+    };
+    // Taking the literal after a name in the same step saves a level of recursion per
+    // map entry.
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] $literal:literal $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [
+                $($items,)*
+                $crate::edn_macro::$make(concat!($($text),+)),
+                $crate::edn_macro::Item::Value($crate::edn_macro::literal($literal)),
+            ]
+            $($rest)*
+        )
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+]) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),])
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] , $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] : $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] : $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] # $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] # $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] ~ $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] ~ $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] _ $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] _ $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] $next:ident $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] $next $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] ( $($list:tt)* ) $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] ( $($list)* ) $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] [ $($vec:tt)* ] $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] [ $($vec)* ] $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] { $($map:tt)* } $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::$make(concat!($($text),+)),] { $($map)* } $($rest)*)
+    };
+    (@name $make:ident [$($items:expr,)*] [$($text:expr),+] $($rest:tt)*) => {
+        $crate::__edn_items!(@ambiguous)
+    };
+    (@ambiguous) => {
+        compile_error!(
+            "edn! cannot tell `:a-b` from `:a -b`: write names with punctuation as strings, like \
+             :\"first-name\", and separate a name from the form after it with `,`"
+        )
+    };
+
+    (@punct +) => { "+" };
+    (@punct *) => { "*" };
+    (@punct /) => { "/" };
+    (@punct =) => { "=" };
+    (@punct ==) => { "==" };
+    (@punct !=) => { "!=" };
+    (@punct <) => { "<" };
+    (@punct <=) => { "<=" };
+    (@punct >) => { ">" };
+    (@punct >=) => { ">=" };
+    (@punct ->) => { "->" };
+    (@punct &) => { "&" };
+    (@punct %) => { "%" };
+    (@punct .) => { "." };
+
+    ([$($items:expr,)*]) => {
+        vec![$($items),*]
+    };
+    ([$($items:expr,)*] , $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)*] $($rest)*)
+    };
+    ([$($items:expr,)*] nil $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::Item::Value($crate::Edn::Nil),] $($rest)*)
+    };
+    ([$($items:expr,)*] true $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::Item::Value($crate::Edn::Bool(true)),] $($rest)*)
+    };
+    ([$($items:expr,)*] false $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::Item::Value($crate::Edn::Bool(false)),] $($rest)*)
+    };
+    ([$($items:expr,)*] ~ $value:tt $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::Item::Value($value.into()),] $($rest)*)
+    };
+    ([$($items:expr,)*] : _ $($rest:tt)*) => {
+        $crate::__edn_items!(@name keyword [$($items,)*] ["_"] $($rest)*)
+    };
+    ([$($items:expr,)*] : $text:literal $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::keyword($text),] $($rest)*)
+    };
+    ([$($items:expr,)*] : $ns:ident / $name:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name keyword [$($items,)*] [stringify!($ns), "/", stringify!($name)] $($rest)*)
+    };
+    ([$($items:expr,)*] : $name:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name keyword [$($items,)*] [stringify!($name)] $($rest)*)
+    };
+    ([$($items:expr,)*] # { $($set:tt)* } $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::set($crate::__edn_items!([] $($set)*)),] $($rest)*
+        )
+    };
+    ([$($items:expr,)*] # $tag:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name tag [$($items,)*] [stringify!($tag)] $($rest)*)
+    };
+    ([$($items:expr,)*] * $name:ident * $($rest:tt)*) => {
+        $crate::__edn_items!(@name symbol [$($items,)*] ["*", stringify!($name), "*"] $($rest)*)
+    };
+    ([$($items:expr,)*] _ $($rest:tt)*) => {
+        $crate::__edn_items!(@name symbol [$($items,)*] ["_"] $($rest)*)
+    };
+    ([$($items:expr,)*] $ns:ident / $name:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name symbol [$($items,)*] [stringify!($ns), "/", stringify!($name)] $($rest)*)
+    };
+    ([$($items:expr,)*] $name:ident $($rest:tt)*) => {
+        $crate::__edn_items!(@name symbol [$($items,)*] [stringify!($name)] $($rest)*)
+    };
+    ([$($items:expr,)*] ( $($list:tt)* ) $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::list($crate::__edn_items!([] $($list)*)),] $($rest)*
+        )
+    };
+    ([$($items:expr,)*] [ $($vec:tt)* ] $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::vec($crate::__edn_items!([] $($vec)*)),] $($rest)*
+        )
+    };
+    ([$($items:expr,)*] { $($map:tt)* } $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::map($crate::__edn_items!([] $($map)*)),] $($rest)*
+        )
+    };
+    ([$($items:expr,)*] - $literal:literal $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::Item::Value($crate::edn_macro::literal(-$literal)),]
+            $($rest)*
+        )
+    };
+    ([$($items:expr,)*] - $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::symbol("-"),] $($rest)*)
+    };
+    ([$($items:expr,)*] $literal:literal $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::Item::Value($crate::edn_macro::literal($literal)),]
+            $($rest)*
+        )
+    };
+    ([$($items:expr,)*] -> > $($rest:tt)*) => {
+        $crate::__edn_items!([$($items,)* $crate::edn_macro::symbol("->>"),] $($rest)*)
+    };
+    ([$($items:expr,)*] $punct:tt $($rest:tt)*) => {
+        $crate::__edn_items!(
+            [$($items,)* $crate::edn_macro::symbol($crate::__edn_items!(@punct $punct)),]
+            $($rest)*
+        )
+    };
+}
+
+/// A form written in [`edn!`]. Tags apply to the value after them once all forms are known.
+#[doc(hidden)]
+pub enum Item {
+    Value(Edn),
+    Tag(&'static str),
+}
+
+/// Rust literals that are also EDN literals.
+#[doc(hidden)]
+pub trait Literal {
+    fn into_edn(self) -> Edn;
+}
+
+impl Literal for i64 {
+    fn into_edn(self) -> Edn {
+        Edn::Int(self)
+    }
+}
+
+impl Literal for f64 {
+    fn into_edn(self) -> Edn {
+        Edn::Float(self.into())
+    }
+}
+
+impl Literal for bool {
+    fn into_edn(self) -> Edn {
+        Edn::Bool(self)
+    }
+}
+
+impl Literal for char {
+    fn into_edn(self) -> Edn {
+        Edn::Char(self)
+    }
+}
+
+impl Literal for &str {
+    fn into_edn(self) -> Edn {
+        Edn::String(self.to_string())
+    }
+}
+
+#[doc(hidden)]
+pub fn literal(literal: impl Literal) -> Edn {
+    literal.into_edn()
+}
+
+#[doc(hidden)]
+pub fn keyword(text: &str) -> Item {
+    match Keyword::new(text) {
+        Ok(keyword) => Item::Value(Edn::Keyword(keyword)),
+        Err(err) => panic!("{err}"),
+    }
+}
+
+#[doc(hidden)]
+pub fn symbol(text: &str) -> Item {
+    match Symbol::new(text) {
+        Ok(symbol) => Item::Value(Edn::Symbol(symbol)),
+        Err(err) => panic!("{err}"),
+    }
+}
+
+#[doc(hidden)]
+pub fn tag(text: &'static str) -> Item {
+    Item::Tag(text)
+}
+
+/// The values of `items`, with every tag applied to the value after it.
+fn values(items: Vec<Item>) -> Vec<Edn> {
+    let mut values = Vec::with_capacity(items.len());
+    for item in items.into_iter().rev() {
+        match item {
+            Item::Value(edn) => values.push(edn),
+            Item::Tag(tag) => {
+                let Some(edn) = values.pop() else {
+                    panic!("#{tag} needs a value to tag");
+                };
+                values.push(match builtin_tag_reader(tag) {
+                    Some(tag_reader) => tag_reader(edn).unwrap_or_else(|err| panic!("{err}")),
+                    None => Edn::TaggedElement(tag.to_string(), Box::new(edn)),
+                });
+            }
+        }
+    }
+    values.reverse();
+    values
+}
+
+#[doc(hidden)]
+pub fn one(items: Vec<Item>) -> Edn {
+    match <[Edn; 1]>::try_from(values(items)) {
+        Ok([edn]) => edn,
+        Err(values) => panic!("edn! expects one form, got {}", values.len()),
+    }
+}
+
+#[doc(hidden)]
+pub fn list(items: Vec<Item>) -> Item {
+    Item::Value(Edn::List(values(items)))
+}
+
+#[doc(hidden)]
+pub fn vec(items: Vec<Item>) -> Item {
+    Item::Value(Edn::Vec(values(items)))
+}
+
+#[doc(hidden)]
+pub fn set(items: Vec<Item>) -> Item {
+    let mut set = BTreeSet::new();
+    for edn in values(items) {
+        if set.contains(&edn) {
+            panic!("Duplicate set element {edn}");
+        }
+        set.insert(edn);
+    }
+    Item::Value(Edn::Set(set))
+}
+
+#[doc(hidden)]
+pub fn map(items: Vec<Item>) -> Item {
+    let mut map = BTreeMap::new();
+    let mut values = values(items).into_iter();
+    while let Some(key) = values.next() {
+        let Some(value) = values.next() else {
+            panic!("Map literal must contain an even number of forms");
+        };
+        if map.contains_key(&key) {
+            panic!("Duplicate map key {key}");
+        }
+        map.insert(key, value);
+    }
+    Item::Value(Edn::Map(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edn_reader::{read_str, read_str_with_options, ReaderOptions};
+    use crate::edn_symbol::Symbol;
+    use crate::Edn;
+
+    fn read(s: &str) -> Edn {
+        read_str(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(edn!(nil), Edn::Nil);
+        assert_eq!(edn!([true false]), read("[true false]"));
+        assert_eq!(
+            edn!([1 -2 1.5 -0.25 "s\n" 'c']),
+            read(r#"[1 -2 1.5 -0.25 "s\n" \c]"#)
+        );
+        assert_eq!(
+            edn!([:a :user/id :type :"valid?" :"a.b/c-d" :a.b/c sym clojure.core/inc]),
+            read("[:a :user/id :type :valid? :a.b/c-d :a.b/c sym clojure.core/inc]")
+        );
+        assert_eq!(
+            edn!({:a 1, :b [1 2 #{:x}] :c nil, "d" (e f)}),
+            read(r#"{:a 1 :b [1 2 #{:x}] :c nil "d" (e f)}"#)
+        );
+        assert_eq!(edn!([:a, -1 a, -2.5]), read("[:a -1 a -2.5]"));
+        assert_eq!(
+            edn!([#inst "2024-01-01T00:00:00Z" #my/tag #other [1]]),
+            read_str_with_options(
+                r#"[#inst "2024-01-01T00:00:00Z" #my/tag #other [1]]"#,
+                ReaderOptions::new().keep_unknown_tags()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let id = 7;
+        let names = read(r#"["a" "b"]"#);
+        assert_eq!(
+            edn!({:id ~id :names ~(names.clone()) :first ~(names[0].clone()) :big ~(u64::MAX) :none ~(None::<bool>)}),
+            read(r#"{:id 7 :names ["a" "b"] :first "a" :big 18446744073709551615 :none nil}"#)
+        );
+    }
+
+    #[test]
+    fn test_symbols() {
+        assert_eq!(
+            edn!([(+ 1 -2) (- a b) (= :_/x _) [a, & rest] (-> x f) (->> x f) *ns* (<= 1 2) /]),
+            read("[(+ 1 -2) (- a b) (= :_/x _) [a & rest] (-> x f) (->> x f) *ns* (<= 1 2) /]")
+        );
+        let swap = Symbol::new("swap!").unwrap();
+        assert_eq!(
+            edn!([(~swap a inc) (~(Symbol::new("-main").unwrap()))]),
+            read("[(swap! a inc) (-main)]")
+        );
+    }
+
+    #[test]
+    fn test_large_literals() {
+        let map = edn!({
+            :"user/attr-aa" 0 :"user/attr-ab" 1 :"user/attr-ac" 2 :"user/attr-ad" 3
+            :"user/attr-ae" 4 :"user/attr-af" 5 :"user/attr-ag" 6 :"user/attr-ah" 7
+            :"user/attr-ai" 8 :"user/attr-aj" 9 :"user/attr-ak" 10 :"user/attr-al" 11
+            :"user/attr-am" 12 :"user/attr-an" 13 :"user/attr-ao" 14 :"user/attr-ap" 15
+            :"user/attr-aq" 16 :"user/attr-ar" 17 :"user/attr-as" 18 :"user/attr-at" 19
+            :"user/attr-au" 20 :"user/attr-av" 21 :"user/attr-aw" 22 :"user/attr-ax" 23
+            :"user/attr-ay" 24 :"user/attr-az" 25 :"user/attr-ba" 26 :"user/attr-bb" 27
+            :"user/attr-bc" 28 :"user/attr-bd" 29
+        });
+        assert_eq!(map.as_map().map(|map| map.len()), Some(30));
+        assert_eq!(map["user/attr-bd"], Edn::Int(29));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate map key :a")]
+    fn test_duplicate_key() {
+        edn!({:a 1 :a 2});
+    }
+}
@@ -1,10 +1,20 @@
 use core::fmt;
 use std::{
+    cmp::Ordering,
     collections::{BTreeMap, BTreeSet, HashMap},
-    iter::Peekable,
+    hash::{Hash, Hasher},
+    io::BufRead,
     str::Chars,
+    sync::Arc,
 };
 
+use crate::edn_error::{EdnError, ErrorKind, Position};
+use crate::edn_inst::Inst;
+use crate::edn_io::Utf8Chars;
+use crate::edn_span::{Shape, Span, SpannedEdn};
+use crate::edn_symbol::{Keyword, Symbol};
+use crate::edn_uuid::Uuid;
+
 use bigdecimal::BigDecimal;
 use lazy_static::lazy_static;
 use num::{BigInt, BigRational, Integer, Num, ToPrimitive};
@@ -12,14 +22,14 @@ use ordered_float::OrderedFloat;
 use regex::Regex;
 use std::str::FromStr;
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Hash)]
+#[derive(Debug, Clone)]
 pub enum Edn {
     Nil,
     Bool(bool),
     String(String),
     Char(char),
-    Symbol(String),
-    Keyword(String),
+    Symbol(Symbol),
+    Keyword(Keyword),
     Int(i64),
     BigInt(BigInt),
     Float(OrderedFloat<f64>),
@@ -29,101 +39,553 @@ pub enum Edn {
     Vec(Vec<Edn>),
     Set(BTreeSet<Edn>),
     Map(BTreeMap<Edn, Edn>),
+    Inst(Inst),
+    Uuid(Uuid),
     TaggedElement(String, Box<Edn>),
+    /// A symbol or collection with metadata attached, e.g. `^:private x`.
+    ///
+    /// Like in Clojure the metadata is ignored by equality, ordering and hashing.
+    WithMeta(BTreeMap<Edn, Edn>, Box<Edn>),
+}
+use Edn::{Bool, Char, Float, Int, Map, Nil, Set, TaggedElement, WithMeta};
+
+impl Edn {
+    pub fn meta(&self) -> Option<&BTreeMap<Edn, Edn>> {
+        match self {
+            WithMeta(meta, _) => Some(meta),
+            _ => None,
+        }
+    }
+
+    /// The value without any metadata attached to it.
+    pub fn without_meta(&self) -> &Edn {
+        let mut edn = self;
+        while let WithMeta(_, inner) = edn {
+            edn = inner;
+        }
+        edn
+    }
+
+    /// Attaches `meta` to the value, replacing any metadata it already had.
+    pub fn with_meta(self, meta: BTreeMap<Edn, Edn>) -> Edn {
+        match self {
+            WithMeta(_, inner) => inner.with_meta(meta),
+            edn if meta.is_empty() => edn,
+            edn => WithMeta(meta, Box::new(edn)),
+        }
+    }
+
+    /// Position of the variant in the ordering between values of different types.
+    fn rank(&self) -> u8 {
+        match self {
+            Nil => 0,
+            Bool(_) => 1,
+            Edn::String(_) => 2,
+            Char(_) => 3,
+            Edn::Symbol(_) => 4,
+            Edn::Keyword(_) => 5,
+            Int(_) => 6,
+            Edn::BigInt(_) => 7,
+            Float(_) => 8,
+            Edn::BigDecimal(_) => 9,
+            Edn::BigRational(_) => 10,
+            Edn::List(_) => 11,
+            Edn::Vec(_) => 12,
+            Set(_) => 13,
+            Map(_) => 14,
+            Edn::Inst(_) => 15,
+            Edn::Uuid(_) => 16,
+            TaggedElement(_, _) => 17,
+            WithMeta(_, edn) => edn.rank(),
+        }
+    }
+}
+
+impl PartialEq for Edn {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Edn {}
+
+impl PartialOrd for Edn {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Edn {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.without_meta(), other.without_meta()) {
+            (Bool(a), Bool(b)) => a.cmp(b),
+            (Edn::String(a), Edn::String(b)) => a.cmp(b),
+            (Char(a), Char(b)) => a.cmp(b),
+            (Edn::Symbol(a), Edn::Symbol(b)) => a.cmp(b),
+            (Edn::Keyword(a), Edn::Keyword(b)) => a.cmp(b),
+            (Int(a), Int(b)) => a.cmp(b),
+            (Edn::BigInt(a), Edn::BigInt(b)) => a.cmp(b),
+            (Float(a), Float(b)) => a.cmp(b),
+            (Edn::BigDecimal(a), Edn::BigDecimal(b)) => a.cmp(b),
+            (Edn::BigRational(a), Edn::BigRational(b)) => a.cmp(b),
+            (Edn::List(a), Edn::List(b)) => a.cmp(b),
+            (Edn::Vec(a), Edn::Vec(b)) => a.cmp(b),
+            (Set(a), Set(b)) => a.cmp(b),
+            (Map(a), Map(b)) => a.cmp(b),
+            (Edn::Inst(a), Edn::Inst(b)) => a.cmp(b),
+            (Edn::Uuid(a), Edn::Uuid(b)) => a.cmp(b),
+            (TaggedElement(a_tag, a), TaggedElement(b_tag, b)) => (a_tag, a).cmp(&(b_tag, b)),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+impl Hash for Edn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let edn = self.without_meta();
+        edn.rank().hash(state);
+        match edn {
+            Nil => {}
+            Bool(b) => b.hash(state),
+            Edn::String(s) => s.hash(state),
+            Edn::Symbol(s) => s.hash(state),
+            Edn::Keyword(s) => s.hash(state),
+            Char(c) => c.hash(state),
+            Int(n) => n.hash(state),
+            Edn::BigInt(n) => n.hash(state),
+            Float(n) => n.hash(state),
+            Edn::BigDecimal(n) => n.hash(state),
+            Edn::BigRational(n) => n.hash(state),
+            Edn::List(vec) | Edn::Vec(vec) => vec.hash(state),
+            Set(set) => set.hash(state),
+            Map(map) => map.hash(state),
+            Edn::Inst(inst) => inst.hash(state),
+            Edn::Uuid(uuid) => uuid.hash(state),
+            TaggedElement(tag, edn) => (tag, edn).hash(state),
+            WithMeta(_, _) => unreachable!("metadata was stripped"),
+        }
+    }
+}
+
+/// Maximum number of nested forms before the reader gives up instead of overflowing the stack.
+pub(crate) const MAX_DEPTH: usize = 256;
+
+/// Reader function for the elements of one tag, see [`ReaderOptions::tag_reader`].
+pub type TagReader = Arc<dyn Fn(Edn) -> EdnResult + Send + Sync>;
+/// Reader function for tags without their own reader, called with the tag and the element.
+pub type DefaultTagReader = Arc<dyn Fn(&str, Edn) -> EdnResult + Send + Sync>;
+
+/// Options for reading EDN, mirroring the opts map of `clojure.edn/read`.
+#[derive(Clone, Default)]
+pub struct ReaderOptions {
+    tag_readers: HashMap<String, TagReader>,
+    default_tag_reader: Option<DefaultTagReader>,
+}
+
+impl ReaderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads elements tagged with `tag` using `f`. Takes precedence over the built-in readers.
+    pub fn tag_reader(
+        mut self,
+        tag: impl Into<String>,
+        f: impl Fn(Edn) -> EdnResult + Send + Sync + 'static,
+    ) -> Self {
+        self.tag_readers.insert(tag.into(), Arc::new(f));
+        self
+    }
+
+    /// Reads elements whose tag has no reader using `f`, like `:default` in `clojure.edn/read`.
+    pub fn default_tag_reader(
+        mut self,
+        f: impl Fn(&str, Edn) -> EdnResult + Send + Sync + 'static,
+    ) -> Self {
+        self.default_tag_reader = Some(Arc::new(f));
+        self
+    }
+
+    /// Keeps elements with unknown tags as [`Edn::TaggedElement`] instead of failing.
+    pub fn keep_unknown_tags(self) -> Self {
+        self.default_tag_reader(|tag, edn| Ok(TaggedElement(tag.to_string(), Box::new(edn))))
+    }
+}
+
+impl fmt::Debug for ReaderOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReaderOptions")
+            .field("tag_readers", &self.tag_readers.keys().collect::<Vec<_>>())
+            .field("default_tag_reader", &self.default_tag_reader.is_some())
+            .finish()
+    }
+}
+
+enum CharSource<'a> {
+    Str(Chars<'a>),
+    Io(Utf8Chars<Box<dyn BufRead + 'a>>),
+}
+
+impl Iterator for CharSource<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            CharSource::Str(chars) => chars.next(),
+            CharSource::Io(chars) => chars.next(),
+        }
+    }
+}
+
+/// Char iterator over the source text that keeps track of the current position.
+pub struct ReaderIter<'a> {
+    chars: CharSource<'a>,
+    options: ReaderOptions,
+    peeked: Option<Option<char>>,
+    position: Position,
+    last_position: Position,
+    depth: usize,
+    /// The spanned forms read so far that are not yet part of an enclosing form, when
+    /// reading with [`read_spanned`].
+    spans: Option<Vec<SpannedEdn>>,
+    /// Keywords and symbols already read, by token, so that repeated ones do not go through
+    /// the global interner. Cleared when it holds [`SYMBOLIC_CACHE_SIZE`] tokens.
+    symbolics: HashMap<String, Edn>,
+}
+
+const SYMBOLIC_CACHE_SIZE: usize = 4096;
+
+impl<'a> ReaderIter<'a> {
+    pub fn new(s: &'a str) -> Self {
+        Self::from_source(CharSource::Str(s.chars()))
+    }
+
+    /// Reads chars incrementally from `reader`, only buffering what `reader` itself buffers.
+    pub fn from_reader(reader: impl BufRead + 'a) -> Self {
+        Self::from_source(CharSource::Io(Utf8Chars::new(Box::new(reader))))
+    }
+
+    pub fn with_options(mut self, options: ReaderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn from_source(chars: CharSource<'a>) -> Self {
+        ReaderIter {
+            chars,
+            options: ReaderOptions::default(),
+            peeked: None,
+            position: Position::default(),
+            last_position: Position::default(),
+            depth: 0,
+            spans: None,
+            symbolics: HashMap::new(),
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&char> {
+        let chars = &mut self.chars;
+        self.peeked.get_or_insert_with(|| chars.next()).as_ref()
+    }
+
+    /// Position of the next char to be read.
+    pub fn current_position(&self) -> Position {
+        self.position
+    }
+
+    /// Position of the char most recently returned by `next`.
+    pub fn last_position(&self) -> Position {
+        self.last_position
+    }
+
+    /// [`interpret_token`], reusing the keywords and symbols this reader has already read.
+    fn interpret_token(&mut self, token: &str) -> EdnRet {
+        if let Some(edn) = self.symbolics.get(token) {
+            return Some(edn.clone());
+        }
+        let edn = interpret_token(token)?;
+        if matches!(edn, Edn::Symbol(_) | Edn::Keyword(_)) {
+            if self.symbolics.len() >= SYMBOLIC_CACHE_SIZE {
+                self.symbolics.clear();
+            }
+            self.symbolics.insert(token.to_string(), edn.clone());
+        }
+        Some(edn)
+    }
+
+    fn error(&self, kind: ErrorKind) -> EdnError {
+        EdnError::new(kind, self.position)
+    }
+
+    /// Takes the io error that ended the input early, if any.
+    fn take_io_error(&mut self) -> Option<EdnError> {
+        let CharSource::Io(chars) = &mut self.chars else {
+            return None;
+        };
+        let err = chars.error.take()?;
+        Some(self.error(ErrorKind::Io(err.to_string())))
+    }
+
+    /// Replaces an EOF or error in `ret` with the io error that ended the input, if any.
+    /// A form that was read completely is returned first, the error is left for the next read.
+    fn io_result(&mut self, ret: EdnResultOption) -> EdnResultOption {
+        match ret {
+            Ok(Some(edn)) => Ok(Some(edn)),
+            ret => self.take_io_error().map_or(ret, Err),
+        }
+    }
+
+    fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, EdnError>,
+    ) -> Result<T, EdnError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(ErrorKind::NestingTooDeep(MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let ret = f(self);
+        self.depth -= 1;
+        ret
+    }
+
+    /// Number of spanned forms recorded so far, to record a form read from here on.
+    fn span_mark(&self) -> usize {
+        self.spans.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Records the span of `edn` read from `start`, made up of the forms recorded since `mark`.
+    fn record_span(&mut self, mark: usize, start: Position, shape: Shape, edn: Edn) -> Edn {
+        let end = self.position;
+        if let Some(spans) = &mut self.spans {
+            let children = spans.split_off(mark);
+            spans.push(SpannedEdn::new(&edn, shape, children, Span { start, end }));
+        }
+        edn
+    }
+
+    /// Drops the spans of forms read since `mark` that are not part of the value, like discards.
+    fn drop_spans(&mut self, mark: usize) {
+        if let Some(spans) = &mut self.spans {
+            spans.truncate(mark);
+        }
+    }
+}
+
+impl Iterator for ReaderIter<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = match self.peeked.take() {
+            Some(ch) => ch?,
+            None => self.chars.next()?,
+        };
+        self.last_position = self.position;
+        self.position.advance(ch);
+        Some(ch)
+    }
 }
-use Edn::{Bool, Char, Float, Int, Keyword, Map, Nil, Set, Symbol, TaggedElement};
 
-type ReaderIter<'a> = Peekable<Chars<'a>>;
 type EdnRet = Option<Edn>;
-type EdnResult = Result<Edn, String>;
-type EdnResultOption = Result<Option<Edn>, String>;
-type Reader = fn(&mut ReaderIter, char) -> EdnResultOption;
+type EdnResult = Result<Edn, EdnError>;
+type EdnResultOption = Result<Option<Edn>, EdnError>;
+type MacroReader = fn(&mut ReaderIter, char) -> EdnResultOption;
 
 lazy_static! {
     static ref symbolPat: Regex = Regex::new(r#"^[:]?([\D&&[^/]].*/)?(/|[\D&&[^/]][^/]*)$"#).unwrap();
     // static ref symbolPat: Regex = Regex::new(r"[:]?((?:[^0-9/].*/)?(/|[^0-9/][^/]*))").unwrap();
-    static ref intPat: Regex = Regex::new("^([-+]?)(?:(0)|([1-9][0-9]*)|0[xX]([0-9A-Fa-f]+)|0([0-7]+)|([1-9][0-9]?)[rR]([0-9A-Za-z]+)|0[0-9]+)(N)?").unwrap();
-    static ref ratioPat: Regex = Regex::new("^([-+]?[0-9]+)/([0-9]+)").unwrap();
-    static ref floatPat: Regex = Regex::new("^([-+]?[0-9]+(\\.[0-9]*)?([eE][-+]?[0-9]+)?)(M)?").unwrap();
+    static ref intPat: Regex = Regex::new("^([-+]?)(?:(0)|([1-9][0-9]*)|0[xX]([0-9A-Fa-f]+)|0([0-7]+)|([1-9][0-9]?)[rR]([0-9A-Za-z]+)|0[0-9]+)(N)?$").unwrap();
+    static ref ratioPat: Regex = Regex::new("^([-+]?[0-9]+)/([0-9]+)$").unwrap();
+    static ref floatPat: Regex = Regex::new("^([-+]?[0-9]+(\\.[0-9]*)?([eE][-+]?[0-9]+)?)(M)?$").unwrap();
 
-    static ref MACROS: HashMap<char, Reader> = {
+    static ref MACROS: HashMap<char, MacroReader> = {
         let mut macros = HashMap::new();
-        macros.insert('"', read_string as Reader);
-        macros.insert(';', read_comment as Reader);
-        macros.insert('^', read_meta as Reader);
-        macros.insert('(', read_list as Reader);
-        macros.insert(')', read_unmatched_delimiter as Reader);
-        macros.insert('[', read_vector as Reader);
-        macros.insert(']', read_unmatched_delimiter as Reader);
-        macros.insert('{', read_map as Reader);
-        macros.insert('}', read_unmatched_delimiter as Reader);
-        macros.insert('\\', read_character as Reader);
-        macros.insert('#', read_dispatch as Reader);
+        macros.insert('"', read_string as MacroReader);
+        macros.insert(';', read_comment as MacroReader);
+        macros.insert('^', read_meta as MacroReader);
+        macros.insert('(', read_list as MacroReader);
+        macros.insert(')', read_unmatched_delimiter as MacroReader);
+        macros.insert('[', read_vector as MacroReader);
+        macros.insert(']', read_unmatched_delimiter as MacroReader);
+        macros.insert('{', read_map as MacroReader);
+        macros.insert('}', read_unmatched_delimiter as MacroReader);
+        macros.insert('\\', read_character as MacroReader);
+        macros.insert('#', read_dispatch as MacroReader);
         macros
     };
-    static ref DISPATCH_MACROS: HashMap<char, Reader> = {
+    static ref DISPATCH_MACROS: HashMap<char, MacroReader> = {
         let mut map = HashMap::new();
-        map.insert('#', read_symbolic_value as Reader);
-        map.insert('^', read_meta as Reader);
-        map.insert('{', read_set as Reader);
-        map.insert('<', read_unreadable as Reader);
-        map.insert('_', read_discard as Reader);
-        map.insert(':', read_namespace_map as Reader);
+        map.insert('#', read_symbolic_value as MacroReader);
+        map.insert('^', read_meta as MacroReader);
+        map.insert('{', read_set as MacroReader);
+        map.insert('<', read_unreadable as MacroReader);
+        map.insert('_', read_discard as MacroReader);
+        map.insert(':', read_namespace_map as MacroReader);
         map
     };
 }
 
+/// Reads the top-level forms of a string or `BufRead` one at a time.
+///
+/// Comments and `#_` discards between forms are skipped. The iterator ends at a clean EOF,
+/// an EOF in the middle of a form is returned as an error. Reading stops after the first error.
+pub struct Reader<'a> {
+    reader: ReaderIter<'a>,
+    done: bool,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(s: &'a str) -> Self {
+        Reader {
+            reader: ReaderIter::new(s),
+            done: false,
+        }
+    }
+
+    pub fn from_reader(reader: impl BufRead + 'a) -> Self {
+        Reader {
+            reader: ReaderIter::from_reader(reader),
+            done: false,
+        }
+    }
+
+    pub fn with_options(mut self, options: ReaderOptions) -> Self {
+        self.reader = self.reader.with_options(options);
+        self
+    }
+}
+
+impl Iterator for Reader<'_> {
+    type Item = EdnResult;
+
+    fn next(&mut self) -> Option<EdnResult> {
+        if self.done {
+            return None;
+        }
+        let ret = self.reader.nested(read_form);
+        let ret = self.reader.io_result(ret).transpose();
+        self.done = !matches!(ret, Some(Ok(_)));
+        ret
+    }
+}
+
 pub fn read_str(s: String) -> EdnResult {
-    let mut reader = s.chars().peekable();
+    let mut reader = ReaderIter::new(&s);
     read(&mut reader, true, Edn::Nil, false)
 }
 
+pub fn read_str_with_options(s: &str, options: ReaderOptions) -> EdnResult {
+    let mut reader = ReaderIter::new(s).with_options(options);
+    read(&mut reader, true, Edn::Nil, false)
+}
+
+/// Reads `s` like [`read_str`], keeping the span of every form including map keys and set
+/// elements.
+pub fn read_spanned(s: &str) -> Result<SpannedEdn, EdnError> {
+    read_spanned_with_options(s, ReaderOptions::default())
+}
+
+pub fn read_spanned_with_options(s: &str, options: ReaderOptions) -> Result<SpannedEdn, EdnError> {
+    let mut reader = ReaderIter::new(s).with_options(options);
+    reader.spans = Some(Vec::new());
+    read(&mut reader, true, Edn::Nil, false)?;
+    let mut spans = reader.spans.unwrap_or_default();
+    Ok(spans.pop().expect("the form read was recorded"))
+}
+
+pub fn read_all(s: &str) -> Result<Vec<Edn>, EdnError> {
+    Reader::new(s).collect()
+}
+
 pub fn read(
     reader: &mut ReaderIter,
-    _eof_is_error: bool,
-    _eof_value: Edn,
+    eof_is_error: bool,
+    eof_value: Edn,
     _is_recursive: bool,
 ) -> EdnResult {
+    let ret = reader.nested(read_form);
+    match reader.io_result(ret)? {
+        Some(edn) => Ok(edn),
+        None if eof_is_error => Err(reader.error(ErrorKind::UnexpectedEof)),
+        None => Ok(eof_value),
+    }
+}
+
+/// Reads the next form, returning `None` if the input ends before one starts.
+fn read_form(reader: &mut ReaderIter) -> EdnResultOption {
     loop {
         // dbg!(reader.clone().collect::<String>());
         skip_whitespace(reader);
-        let ch = reader.next().ok_or("EOF while reading")?;
+        let Some(ch) = reader.next() else {
+            return Ok(None);
+        };
+        let start = reader.last_position();
+        let mark = reader.span_mark();
 
         if ch.is_ascii_digit() {
-            return read_number(reader, ch);
+            let edn = read_number(reader, ch)?;
+            return Ok(Some(reader.record_span(mark, start, Shape::Scalar, edn)));
         }
 
-        if let Some(macro_) = MACROS.get(&ch) {
-            let ret = macro_(reader, ch)?;
-            match ret {
-                Some(ret) => return Ok(ret),
+        if MACROS.contains_key(&ch) {
+            match read_macro(reader, ch)? {
+                Some(ret) => return Ok(Some(ret)),
                 None => continue,
             }
         }
 
-        if (ch == '+' || ch == '-') && reader.peek().expect("EOF while reading").is_ascii_digit() {
-            return read_number(reader, ch);
+        if (ch == '+' || ch == '-') && reader.peek().is_some_and(|c| c.is_ascii_digit()) {
+            let edn = read_number(reader, ch)?;
+            return Ok(Some(reader.record_span(mark, start, Shape::Scalar, edn)));
         }
 
         let token = read_token(reader, ch, true)?;
-        return interpret_token(token);
+        return match reader.interpret_token(&token) {
+            Some(edn) => Ok(Some(reader.record_span(mark, start, Shape::Scalar, edn))),
+            None => Err(EdnError::new(ErrorKind::InvalidToken(token), start)),
+        };
     }
 }
 
-fn interpret_token(token: String) -> EdnResult {
-    let token = match token.as_str() {
-        "nil" => Nil,
-        "true" => Bool(true),
-        "false" => Bool(false),
-        s => {
-            let ret = match_symbol(s);
-            match ret {
-                Some(sym) => sym,
-                None => return Err(format!("Invalid token: {s}")),
-            }
+/// Calls the reader macro for `ch`, recording the span of the form it returns.
+fn read_macro(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
+    let start = reader.last_position();
+    let mark = reader.span_mark();
+    let shape = Shape::of_macro(ch, reader.peek().copied());
+    match MACROS[&ch](reader, ch)? {
+        Some(edn) => Ok(Some(reader.record_span(mark, start, shape, edn))),
+        None => {
+            reader.drop_spans(mark);
+            Ok(None)
         }
-    };
-    Ok(token)
+    }
+}
+
+/// Reads `token` as a symbol or keyword if the reader reads all of it as one.
+pub(crate) fn read_symbolic(token: &str) -> EdnRet {
+    let mut chars = token.chars();
+    let first = chars.next()?;
+    let starts_number = first.is_ascii_digit()
+        || (first == '+' || first == '-')
+            && chars.clone().next().is_some_and(|c| c.is_ascii_digit());
+    if starts_number || is_macro(first) || non_constituent(first) {
+        return None;
+    }
+    if token
+        .chars()
+        .any(|ch| is_whitespace(ch) || is_terminating_macro(ch) || non_constituent(ch))
+    {
+        return None;
+    }
+    interpret_token(token).filter(|edn| matches!(edn, Edn::Symbol(_) | Edn::Keyword(_)))
+}
+
+fn interpret_token(token: &str) -> EdnRet {
+    match token {
+        "nil" => Some(Nil),
+        "true" => Some(Bool(true)),
+        "false" => Some(Bool(false)),
+        s => match_symbol(s),
+    }
 }
 
 fn match_symbol(s: &str) -> EdnRet {
@@ -133,7 +595,7 @@ fn match_symbol(s: &str) -> EdnRet {
         let name = caps.get(2).unwrap().as_str();
         if ns.is_some_and(|ns| ns.as_str().ends_with(":/"))
             || name.ends_with(":")
-            || s[1..].contains("::")
+            || s[s.chars().next().map_or(0, char::len_utf8)..].contains("::")
         {
             return None;
         }
@@ -141,11 +603,11 @@ fn match_symbol(s: &str) -> EdnRet {
             return None;
         }
         let is_keyword = s.starts_with(":");
-        let sym = s[if is_keyword { 1 } else { 0 }..].to_string();
+        let sym = &s[if is_keyword { 1 } else { 0 }..];
         if is_keyword {
-            Some(Keyword(sym))
+            Some(Edn::Keyword(Keyword::new_unchecked(sym)))
         } else {
-            Some(Symbol(sym))
+            Some(Edn::Symbol(Symbol::new_unchecked(sym)))
         }
     } else {
         None
@@ -155,6 +617,7 @@ fn match_symbol(s: &str) -> EdnRet {
 // Readers
 
 fn read_number(reader: &mut ReaderIter, ch: char) -> EdnResult {
+    let start = reader.last_position();
     let mut s = ch.to_string();
 
     loop {
@@ -168,12 +631,19 @@ fn read_number(reader: &mut ReaderIter, ch: char) -> EdnResult {
         }
     }
 
-    match_number(&s).ok_or(format!("Invalid number: {s}"))
+    match_number(&s).ok_or_else(|| EdnError::new(ErrorKind::InvalidNumber(s), start))
 }
 
-fn read_token(reader: &mut ReaderIter, ch: char, lead_constituent: bool) -> Result<String, String> {
+fn read_token(
+    reader: &mut ReaderIter,
+    ch: char,
+    lead_constituent: bool,
+) -> Result<String, EdnError> {
     if lead_constituent && non_constituent(ch) {
-        return Err(format!("Invalid leading leading character: {ch}"));
+        return Err(EdnError::new(
+            ErrorKind::InvalidLeadingCharacter(ch),
+            reader.last_position(),
+        ));
     }
     let mut out = ch.to_string();
 
@@ -183,7 +653,7 @@ fn read_token(reader: &mut ReaderIter, ch: char, lead_constituent: bool) -> Resu
             None => return Ok(out),
             Some(&ch) if is_whitespace(ch) || is_terminating_macro(ch) => return Ok(out),
             Some(&ch) if non_constituent(ch) => {
-                return Err(format!("Invalid constituent character: {ch}"))
+                return Err(reader.error(ErrorKind::InvalidConstituentCharacter(ch)))
             }
             Some(&ch) => {
                 out.push(ch);
@@ -199,13 +669,15 @@ fn read_string(reader: &mut ReaderIter, double_quote: char) -> EdnResultOption {
         unreachable!("Started reading string with {double_quote} but it should always be a \"");
     }
 
+    let start = reader.last_position();
     let mut out = String::new();
     loop {
-        let ch = match reader.next().expect("EOF while reading string") {
+        let eof = || EdnError::new(ErrorKind::UnterminatedString, start);
+        let ch = match reader.next().ok_or_else(eof)? {
             '"' => break,
             '\\' => {
                 // escape
-                match reader.next().expect("EOF while reading string") {
+                match reader.next().ok_or_else(eof)? {
                     't' => '\t',
                     'r' => '\r',
                     'n' => '\n',
@@ -214,23 +686,49 @@ fn read_string(reader: &mut ReaderIter, double_quote: char) -> EdnResultOption {
                     'b' => '\u{08}',
                     'f' => '\u{0C}',
                     'u' => {
-                        let ch = reader.next().unwrap();
-                        if !ch.is_ascii_hexdigit() {
-                            return Err(format!("Unvalid unicode escape: \\u{ch}"));
-                        }
-                        read_unicode_char(reader, ch, 16, 4, true).unwrap()
+                        let escape_start = reader.last_position();
+                        let unit = read_unicode_escape(reader, start)?;
+                        // Chars outside the BMP are escaped as a UTF-16 surrogate pair.
+                        let code =
+                            if (0xD800..0xDC00).contains(&unit) && reader.peek() == Some(&'\\') {
+                                let _ = reader.next();
+                                let low = match reader.next().ok_or_else(eof)? {
+                                    'u' => read_unicode_escape(reader, start)?,
+                                    _ => 0,
+                                };
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(EdnError::new(
+                                        ErrorKind::InvalidEscape(format!("u{unit:04x}")),
+                                        escape_start,
+                                    ));
+                                }
+                                0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                unit
+                            };
+                        char::from_u32(code).ok_or_else(|| {
+                            EdnError::new(
+                                ErrorKind::InvalidEscape(format!("u{unit:04x}")),
+                                escape_start,
+                            )
+                        })?
                     }
                     ch => {
+                        let escape_start = reader.last_position();
                         if ch.is_ascii_digit() {
-                            let ch = read_unicode_char(reader, ch, 8, 3, false).unwrap();
-                            if (ch as u32) > 0o377 {
-                                return Err(
-                                    "Octal escape sequence must be in range [0, 377].".to_string()
-                                );
+                            let c = read_unicode_char(reader, ch, 8, 3, false)?;
+                            if (c as u32) > 0o377 {
+                                return Err(EdnError::new(
+                                    ErrorKind::InvalidEscape(format!("{:o}", c as u32)),
+                                    escape_start,
+                                ));
                             }
-                            ch
+                            c
                         } else {
-                            return Err(format!("Unsupported escape character: \\{ch}"));
+                            return Err(EdnError::new(
+                                ErrorKind::InvalidEscape(ch.to_string()),
+                                escape_start,
+                            ));
                         }
                     }
                 }
@@ -242,43 +740,76 @@ fn read_string(reader: &mut ReaderIter, double_quote: char) -> EdnResultOption {
     Ok(Some(Edn::String(out)))
 }
 
+/// Reads the four hex digits after `\u` in a string, which may be half of a surrogate pair.
+fn read_unicode_escape(reader: &mut ReaderIter, string_start: Position) -> Result<u32, EdnError> {
+    let start = reader.last_position();
+    let ch = reader
+        .next()
+        .ok_or_else(|| EdnError::new(ErrorKind::UnterminatedString, string_start))?;
+    if !ch.is_ascii_hexdigit() {
+        return Err(EdnError::new(
+            ErrorKind::InvalidEscape(format!("u{ch}")),
+            start,
+        ));
+    }
+    read_code_point(reader, ch, 16, 4, true)
+}
+
 fn read_unicode_char(
     reader: &mut ReaderIter,
     ch: char,
     base: u32,
     length: i32,
     exact: bool,
-) -> Result<char, String> {
-    let mut uc = {
-        let uc = ch.to_digit(base);
-        if uc.is_none() {
-            return Err(format!("Invalid digit: {ch}"));
-        }
-        uc.unwrap()
+) -> Result<char, EdnError> {
+    let start = reader.last_position();
+    let escape = if base == 16 {
+        format!("u{ch}")
+    } else {
+        ch.to_string()
     };
-    let mut i = 0;
-    for curr in 0..length {
-        i = curr;
+    let uc = read_code_point(reader, ch, base, length, exact)?;
+    char::from_u32(uc).ok_or_else(|| EdnError::new(ErrorKind::InvalidEscape(escape), start))
+}
+
+fn read_code_point(
+    reader: &mut ReaderIter,
+    ch: char,
+    base: u32,
+    length: i32,
+    exact: bool,
+) -> Result<u32, EdnError> {
+    let start = reader.last_position();
+    let mut escape = if base == 16 {
+        format!("u{ch}")
+    } else {
+        ch.to_string()
+    };
+    let mut uc = ch
+        .to_digit(base)
+        .ok_or_else(|| EdnError::new(ErrorKind::InvalidEscape(escape.clone()), start))?;
+    let mut i = 1;
+    while i < length {
         let ch = reader.peek();
         match ch {
             None => break,
             Some(&ch) if is_whitespace(ch) || is_macro(ch) => break,
             Some(&ch) => {
                 let _ = reader.next();
+                escape.push(ch);
                 let d = ch.to_digit(base);
                 match d {
-                    None => return Err(format!("Invalid digit: {ch}")),
+                    None => return Err(EdnError::new(ErrorKind::InvalidEscape(escape), start)),
                     Some(d) => uc = uc * base + d,
                 }
             }
         }
+        i += 1;
     }
     if i != length && exact {
-        return Err(format!(
-            "Invalid character length: {i}, should be: {length}"
-        ));
+        return Err(EdnError::new(ErrorKind::InvalidEscape(escape), start));
     }
-    char::from_u32(uc).ok_or(format!("Invalid character: {uc}"))
+    Ok(uc)
 }
 
 fn read_comment(reader: &mut ReaderIter, semicolon: char) -> EdnResultOption {
@@ -298,8 +829,11 @@ fn read_list(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
     Ok(Some(Edn::List(list)))
 }
 
-fn read_unmatched_delimiter(_reader: &mut ReaderIter, ch: char) -> EdnResultOption {
-    Err(format!("Unmatched Delimiter: {ch}"))
+fn read_unmatched_delimiter(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
+    Err(EdnError::new(
+        ErrorKind::UnmatchedDelimiter(ch),
+        reader.last_position(),
+    ))
 }
 fn read_vector(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
     assert_eq!(ch, '[');
@@ -308,113 +842,190 @@ fn read_vector(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
 }
 fn read_map(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
     assert_eq!(ch, '{');
+    let start = reader.last_position();
     let vec = read_delimited_list('}', reader, true)?;
-    if vec.len().is_odd() {
-        Err("Map literal must contain an even number of forms".to_string())
-    } else {
-        let map = vec
-            .chunks_exact(2)
-            .map(|chunk| (chunk[0].clone(), chunk[1].clone()))
-            .collect();
-        Ok(Some(Map(map)))
-    }
+    let map = collect_map(vec, start, |k| k)?;
+    Ok(Some(Map(map)))
 }
 
 fn read_character(reader: &mut ReaderIter, backslash: char) -> EdnResultOption {
     assert_eq!(backslash, '\\');
+    let start = reader.last_position();
     // let token = reader.next().and_then(|x| read_token(reader, x, false));
     let token = {
-        let t = reader.next().expect("EOF while reading");
+        let t = reader
+            .next()
+            .ok_or_else(|| reader.error(ErrorKind::UnexpectedEof))?;
         read_token(reader, t, false)?
     };
     let c = match token.as_ref() {
-        t if t.len() == 1 => t.chars().next().unwrap(),
+        t if t.chars().count() == 1 => t.chars().next().unwrap(),
         "newline" => '\n',
         "space" => ' ',
         "tab" => '\t',
         "backspace" => '\u{08}',
         "formfeed" => '\u{0C}',
         "return" => '\r',
-        t if t.starts_with("u") => {
-            let c = read_unicode_char_from_token(t, 1, 4, 16)?;
+        t if t.starts_with('u') => {
+            let c = read_unicode_char_from_token(t, 1, 4, 16);
             match c {
-                None => return Err(format!("Invalid character constant: \\{t}")),
-                Some(c) if (c as u32) >= 0xD800 && (c as u32) <= 0xDFFF => {
-                    return Err(format!("Invalid character constant: \\u{c}"))
-                }
-                Some(c) => c,
+                Some(c) if !(0xD800..=0xDFFF).contains(&(c as u32)) => c,
+                _ => return Err(EdnError::new(ErrorKind::InvalidCharacter(token), start)),
             }
         }
-        t if t.starts_with("o") => {
-            todo!()
+        t if t.starts_with('o') => {
+            let c = read_unicode_char_from_token(t, 1, t.len() - 1, 8);
+            match c {
+                Some(c) if t.len() <= 4 && (c as u32) <= 0o377 => c,
+                _ => return Err(EdnError::new(ErrorKind::InvalidCharacter(token), start)),
+            }
         }
-        t => return Err(format!("Unsupported character: \\{t}")),
+        _ => return Err(EdnError::new(ErrorKind::InvalidCharacter(token), start)),
     };
     Ok(Some(Char(c)))
 }
 
 fn read_dispatch(reader: &mut ReaderIter, hash: char) -> EdnResultOption {
     assert_eq!(hash, '#');
-    let ch = *reader.peek().expect("EOF while reading character");
+    let Some(&ch) = reader.peek() else {
+        return Err(reader.error(ErrorKind::UnexpectedEof));
+    };
     if let Some(macro_) = DISPATCH_MACROS.get(&ch) {
         let ch = reader.next().unwrap();
-        return macro_(reader, ch);
+        macro_(reader, ch)
     } else if ch.is_alphabetic() {
-        return read_tagged(reader, ch).map(Some);
+        read_tagged(reader, ch).map(Some)
     } else {
-        return Err(format!("No dispatch macro for: {ch}"));
+        Err(reader.error(ErrorKind::NoDispatchMacro(ch)))
     }
 }
 
 fn read_tagged(reader: &mut ReaderIter, ch: char) -> EdnResult {
     assert!(ch.is_alphabetic());
+    let start = reader.current_position();
     let name = read(reader, true, Nil, false)?;
-    if let Symbol(name) = name {
+    if let Edn::Symbol(name) = name {
+        let name = name.as_str();
         let o = read(reader, true, Nil, true)?;
-        if !["uuid", "inst"].contains(&name.as_str()) {
-            return Err(format!("No reader function for tag {name}"));
-        }
-        Ok(TaggedElement(name, Box::new(o)))
+        let options = &reader.options;
+        let ret = if let Some(tag_reader) = options.tag_readers.get(name) {
+            tag_reader(o)
+        } else if let Some(tag_reader) = builtin_tag_reader(name) {
+            tag_reader(o)
+        } else if let Some(default_tag_reader) = &options.default_tag_reader {
+            default_tag_reader(name, o)
+        } else {
+            Err(EdnError::new(
+                ErrorKind::UnknownTag(name.to_string()),
+                start,
+            ))
+        };
+        ret.map_err(|err| err.with_position(start))
     } else {
-        Err("Reader tag must be a symbol".to_string())
+        Err(EdnError::new(ErrorKind::InvalidTag(name), start))
     }
 }
 
+pub(crate) fn builtin_tag_reader(tag: &str) -> Option<fn(Edn) -> EdnResult> {
+    match tag {
+        "inst" => Some(read_inst),
+        "uuid" => Some(read_uuid),
+        _ => None,
+    }
+}
+
+fn read_inst(o: Edn) -> EdnResult {
+    let Edn::String(s) = o else {
+        return Err(ErrorKind::InvalidTaggedValue(
+            "Instance literal expects a string for its timestamp.".to_string(),
+        )
+        .into());
+    };
+    s.parse()
+        .map(Edn::Inst)
+        .map_err(|err| ErrorKind::InvalidTaggedValue(err.to_string()).into())
+}
+
+fn read_uuid(o: Edn) -> EdnResult {
+    let Edn::String(s) = o else {
+        return Err(ErrorKind::InvalidTaggedValue(
+            "UUID literal expects a string as its representation.".to_string(),
+        )
+        .into());
+    };
+    s.parse()
+        .map(Edn::Uuid)
+        .map_err(|err| ErrorKind::InvalidTaggedValue(err.to_string()).into())
+}
+
 // Dispatch Macros
 fn read_symbolic_value(reader: &mut ReaderIter, quote: char) -> EdnResultOption {
     assert_eq!(quote, '#');
+    let start = reader.current_position();
     let edn = read(reader, true, Nil, true)?;
     let out = match edn {
-        Symbol(s) => match s.as_ref() {
+        Edn::Symbol(s) => match s.as_str() {
             "Inf" => Edn::Float(f64::INFINITY.into()),
             "-Inf" => Edn::Float(f64::NEG_INFINITY.into()),
             "NaN" => Edn::Float(f64::NAN.into()),
-            _ => return Err(format!("Unkown symbolic value: ##{s}")),
+            _ => {
+                return Err(EdnError::new(
+                    ErrorKind::UnknownSymbolicValue(s.to_string()),
+                    start,
+                ))
+            }
         },
-        _ => return Err(format!("Invalid token: ##{edn:?}")),
+        _ => {
+            return Err(EdnError::new(
+                ErrorKind::InvalidToken(format!("##{edn}")),
+                start,
+            ))
+        }
     };
     Ok(Some(out))
 }
 
 fn read_meta(reader: &mut ReaderIter, carrot: char) -> EdnResultOption {
     assert_eq!(carrot, '^');
-    // TODO: Implement metadata
-    let meta = read(reader, true, Nil, true)?;
-    match meta {
-        Symbol(_) | Edn::String(_) | Keyword(_) | Map(_) => {}
-        _ => return Err("Metadata must be Symbol,Keyword,String or Map".to_string()),
+    let start = reader.current_position();
+    let meta = match read(reader, true, Nil, true)? {
+        tag @ (Edn::Symbol(_) | Edn::String(_)) => {
+            BTreeMap::from([(Edn::Keyword(Keyword::new_unchecked("tag")), tag)])
+        }
+        kw @ Edn::Keyword(_) => BTreeMap::from([(kw, Bool(true))]),
+        Map(map) => map,
+        meta => return Err(EdnError::new(ErrorKind::InvalidMetadata(meta), start)),
+    };
+    let o = read(reader, true, Nil, true)?;
+    match o.without_meta() {
+        Edn::Symbol(_) | Edn::List(_) | Edn::Vec(_) | Set(_) | Map(_) => {}
+        _ => return Err(EdnError::new(ErrorKind::InvalidMetadataTarget(o), start)),
     }
-    read(reader, true, Nil, true).map(Some)
+    // Stacked metadata is merged, with the outer entries taking precedence.
+    let mut merged = o.meta().cloned().unwrap_or_default();
+    merged.extend(meta);
+    Ok(Some(o.with_meta(merged)))
 }
 
 fn read_set(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
     assert_eq!(ch, '{');
+    let start = reader.last_position();
     let vec = read_delimited_list('}', reader, true)?;
-    Ok(Some(Set(vec.into_iter().collect())))
+    let mut set = BTreeSet::new();
+    for item in vec {
+        if set.contains(&item) {
+            return Err(EdnError::new(ErrorKind::DuplicateKey(item), start));
+        }
+        set.insert(item);
+    }
+    Ok(Some(Set(set)))
 }
 
-fn read_unreadable(_reader: &mut ReaderIter, _ch: char) -> EdnResultOption {
-    Err("Unreadable form".to_string())
+fn read_unreadable(reader: &mut ReaderIter, _ch: char) -> EdnResultOption {
+    Err(EdnError::new(
+        ErrorKind::UnreadableForm,
+        reader.last_position(),
+    ))
 }
 
 fn read_discard(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
@@ -425,61 +1036,77 @@ fn read_discard(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
 
 fn read_namespace_map(reader: &mut ReaderIter, ch: char) -> EdnResultOption {
     assert_eq!(ch, ':');
-    let sym = read(reader, true, Nil, false);
-    let namespace = {
-        if let Ok(Symbol(sym)) = sym {
-            let (ns, name) = sym_split(&sym).expect("Symbol has valid sym");
-            if !ns.is_none() {
-                return Err(format!(
-                    "Namespaced map must specify a valid namespace: {sym}",
-                ));
-            }
-            Some(name.to_string())
-        } else {
-            None
+    let start = reader.last_position();
+    let namespace = match read(reader, true, Nil, false)? {
+        Edn::Symbol(sym) if sym.namespace().is_none() => sym.as_str().to_string(),
+        Edn::Symbol(sym) => {
+            return Err(EdnError::new(
+                ErrorKind::InvalidNamespace(sym.to_string()),
+                start,
+            ))
+        }
+        edn => {
+            return Err(EdnError::new(
+                ErrorKind::InvalidNamespace(edn.to_string()),
+                start,
+            ))
         }
     };
     skip_whitespace(reader);
-    let ch = reader.next().expect("EOF while reading");
+    let ch = reader
+        .next()
+        .ok_or_else(|| reader.error(ErrorKind::UnexpectedEof))?;
     if ch != '{' {
-        return Err("Namespaced map must specify a map".to_string());
+        return Err(EdnError::new(
+            ErrorKind::NamespacedMapMissingMap,
+            reader.last_position(),
+        ));
     }
     let vec = read_delimited_list('}', reader, true)?;
+    let map = collect_map(vec, start, |key| qualify_key(&namespace, key))?;
+    Ok(Some(Map(map)))
+}
+
+/// Applies the namespace of a `#:ns{}` map to one of its keys.
+pub(crate) fn qualify_key(namespace: &str, key: Edn) -> Edn {
+    // `:_/name` keys opt out of the namespace.
+    let qualify = |ns: Option<&str>, name: &str| match ns {
+        Some("_") => Some(name.to_string()),
+        None => Some(format!("{namespace}/{name}")),
+        Some(_) => None,
+    };
+    match key {
+        Edn::Keyword(kw) => match qualify(kw.namespace(), kw.name()) {
+            Some(text) => Edn::Keyword(Keyword::new_unchecked(&text)),
+            None => Edn::Keyword(kw),
+        },
+        Edn::Symbol(sym) => match qualify(sym.namespace(), sym.name()) {
+            Some(text) => Edn::Symbol(Symbol::new_unchecked(&text)),
+            None => Edn::Symbol(sym),
+        },
+        key => key,
+    }
+}
+
+/// Pairs up the forms of a map literal starting at `start`, rejecting odd counts and duplicate keys.
+fn collect_map(
+    vec: Vec<Edn>,
+    start: Position,
+    mut key_fn: impl FnMut(Edn) -> Edn,
+) -> Result<BTreeMap<Edn, Edn>, EdnError> {
     if vec.len().is_odd() {
-        return Err("Map literal must contain an even number of forms".to_string());
-    } else {
-        let map = vec
-            .chunks_exact(2)
-            .map(|chunk| (chunk[0].clone(), chunk[1].clone()))
-            .map(|chunk| {
-                if let Some(namespace) = namespace.as_deref() {
-                    let new_key = match chunk.0 {
-                        Keyword(kw) => {
-                            let (ns, name) = sym_split(&kw).unwrap();
-                            match ns {
-                                Some(ns) if ns == "_/" => Keyword(format!("{name}")),
-                                None => Keyword(format!("{namespace}/{name}")),
-                                _ => Keyword(kw),
-                            }
-                        }
-                        Symbol(sym) => {
-                            let (ns, name) = sym_split(&sym).unwrap();
-                            match ns {
-                                Some(ns) if ns == "_/" => Symbol(format!("{name}")),
-                                None => Symbol(format!("{namespace}/{name}")),
-                                _ => Symbol(sym),
-                            }
-                        }
-                        _ => chunk.0,
-                    };
-                    (new_key, chunk.1)
-                } else {
-                    chunk
-                }
-            })
-            .collect();
-        Ok(Some(Map(map)))
+        return Err(EdnError::new(ErrorKind::OddMap, start));
     }
+    let mut map = BTreeMap::new();
+    let mut iter = vec.into_iter();
+    while let (Some(k), Some(v)) = (iter.next(), iter.next()) {
+        let k = key_fn(k);
+        if map.contains_key(&k) {
+            return Err(EdnError::new(ErrorKind::DuplicateKey(k), start));
+        }
+        map.insert(k, v);
+    }
+    Ok(map)
 }
 
 // Matches
@@ -489,7 +1116,7 @@ fn match_number(s: &str) -> EdnRet {
     if let Some(caps) = caps {
         if caps.get(2).is_some() {
             if caps.get(8).is_some() {
-                return Some(Edn::String("BigInt.ZERO".to_string()));
+                return Some(Edn::BigInt(BigInt::from(0)));
             } else {
                 return Some(Int(0));
             }
@@ -511,13 +1138,13 @@ fn match_number(s: &str) -> EdnRet {
         }
         if let Some(m) = caps.get(7) {
             n = m.as_str().into();
-            radix = caps.get(6).map(|x| x.as_str().parse()).unwrap().unwrap();
+            radix = caps.get(6)?.as_str().parse().ok()?;
         }
 
-        if n == String::new() {
+        if n.is_empty() || !(2..=36).contains(&radix) {
             return None;
         }
-        let mut bn = BigInt::from_str_radix(&n, radix).unwrap();
+        let mut bn = BigInt::from_str_radix(&n, radix).ok()?;
         if negate {
             bn *= -1;
         }
@@ -534,16 +1161,16 @@ fn match_number(s: &str) -> EdnRet {
     let caps = floatPat.captures(s);
     if let Some(caps) = caps {
         if caps.get(4).is_some() {
-            let bd = BigDecimal::from_str(caps.get(1).unwrap().as_str()).unwrap();
+            let bd = BigDecimal::from_str(caps.get(1)?.as_str()).ok()?;
             return Some(Edn::BigDecimal(bd));
         } else {
-            return Some(Float(s.parse().unwrap()));
+            return Some(Float(s.parse().ok()?));
         }
     }
 
     let caps = ratioPat.captures(s);
     if caps.is_some() {
-        let ratio = BigRational::from_str(s).unwrap();
+        let ratio = BigRational::from_str(s).ok()?;
         return Some(Edn::BigRational(ratio));
     }
     None
@@ -553,21 +1180,29 @@ fn read_delimited_list(
     delim: char,
     reader: &mut ReaderIter,
     is_recursive: bool,
-) -> Result<Vec<Edn>, String> {
+) -> Result<Vec<Edn>, EdnError> {
+    reader.nested(|reader| read_delimited_forms(delim, reader, is_recursive))
+}
+
+fn read_delimited_forms(
+    delim: char,
+    reader: &mut ReaderIter,
+    is_recursive: bool,
+) -> Result<Vec<Edn>, EdnError> {
+    let start = reader.last_position();
     let mut list = Vec::new();
     loop {
         skip_whitespace(reader);
         match reader.peek() {
-            None => return Err("EOF while reading".to_string()),
+            None => return Err(EdnError::new(ErrorKind::UnexpectedEof, start)),
             Some(&ch) if ch == delim => {
                 let _ = reader.next();
                 break;
             }
             Some(&ch) => {
-                if let Some(macro_) = MACROS.get(&ch) {
+                if MACROS.contains_key(&ch) {
                     let _ = reader.next();
-                    let ret = macro_(reader, ch)?;
-                    if let Some(ret) = ret {
+                    if let Some(ret) = read_macro(reader, ch)? {
                         list.push(ret);
                     }
                 } else {
@@ -585,22 +1220,17 @@ fn read_unicode_char_from_token(
     offset: usize,
     length: usize,
     base: u32,
-) -> Result<Option<char>, String> {
+) -> Option<char> {
     if token.len() != offset + length {
-        return Err(format!("Invalid unicode character: \\{token}"));
+        return None;
     }
 
     let mut uc = 0;
     for d in token.chars().skip(offset) {
-        match d.to_digit(base) {
-            None => return Err(format!("Invalid digit: {d}")),
-            Some(d) => {
-                uc = uc * base + d;
-            }
-        }
+        uc = uc * base + d.to_digit(base)?;
     }
 
-    Ok(char::from_u32(uc))
+    char::from_u32(uc)
 }
 // Utils
 fn non_constituent(ch: char) -> bool {
@@ -619,73 +1249,235 @@ fn is_macro(ch: char) -> bool {
     MACROS.get(&ch).is_some()
 }
 
-impl fmt::Display for Edn {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Nil => write!(f, "nil"),
-            Bool(b) => write!(f, "{b}"),
-            Edn::String(s) => write!(
-                f,
-                "\"{}\"",
-                s.replace("\r\n", "\n")
-                    .chars()
-                    .flat_map(|c| c.escape_default())
-                    .collect::<String>()
-                    .replace("\\'", "'")
-            ),
-            Char(' ') => write!(f, "\\space"),
-            Char(c) => write!(f, "\\{c}"),
-            Symbol(s) => write!(f, "{s}"),
-            Keyword(k) => write!(f, ":{k}"),
-            Int(n) => write!(f, "{n}"),
-            Edn::BigInt(n) => write!(f, "{n}N"),
-            Float(n) => write!(f, "{n}"),
-            Edn::BigDecimal(n) => write!(f, "{n}M"),
-            Edn::BigRational(n) => write!(f, "{n}R"),
-            Edn::List(vec) => write_delimited_list(f, "(", vec, ")"),
-            Edn::Vec(vec) => write_delimited_list(f, "[", vec, "]"),
-            Set(btree_set) => write_delimited_list(f, "#{", btree_set, "}"),
-            Map(map) => {
-                write!(f, "{{")?;
-                let fmt_map = map
-                    .iter()
-                    .map(|(k, v)| format!("{k} {v}"))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                write!(f, "{fmt_map}")?;
-                write!(f, "}}")
-            }
-            TaggedElement(tag, edn) => write!(f, "#{tag} {edn}"),
-        }
+fn skip_whitespace(reader: &mut ReaderIter) {
+    while reader.peek().is_some_and(|&x| is_whitespace(x)) {
+        let _ = reader.next().expect("whitespace does not end reader iter");
     }
 }
 
-fn write_delimited_list<'a, I: IntoIterator<Item = &'a Edn>>(
-    f: &mut std::fmt::Formatter<'_>,
-    start: &str,
-    iter: I,
-    end: &str,
-) -> Result<(), std::fmt::Error> {
-    write!(f, "{start}")?;
-    for (i, item) in iter.into_iter().enumerate() {
-        if i > 0 {
-            write!(f, " ")?;
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn read_err(s: &str) -> EdnError {
+        read_str(s.to_string()).unwrap_err()
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let err = read_err("[1 2\n {:a 1 :b}]");
+        assert_eq!(err.kind(), &ErrorKind::OddMap);
+        assert_eq!((err.offset(), err.line(), err.column()), (6, 2, 2));
+
+        let err = read_err("(1 2\n  3");
+        assert_eq!(err.kind(), &ErrorKind::UnexpectedEof);
+        assert_eq!((err.line(), err.column()), (1, 1));
+
+        let err = read_err("[\"λ\" 1)");
+        assert_eq!(err.kind(), &ErrorKind::UnmatchedDelimiter(')'));
+        assert_eq!((err.offset(), err.column()), (7, 7));
+
+        let err = read_err("{:a 1 :a 2}");
+        assert_eq!(
+            err.kind(),
+            &ErrorKind::DuplicateKey(Edn::Keyword(Keyword::new_unchecked("a")))
+        );
+    }
+
+    #[test]
+    fn test_malformed_input_is_err() {
+        for s in [
+            "\"abc", "\"\\", "\"\\u", "\"\\u00", "#", "\\", "\\o12x", "\\o400", "#:a", "#:a{:1 2",
+            "#:{:a 1}", "2r3", "99r1", "1/0", "1/2x", "1.5x", "λ::a", "^", "#_",
+        ] {
+            assert!(read_str(s.to_string()).is_err(), "{s} should not parse");
         }
-        write!(f, "{item}")?;
+        assert_eq!(
+            read_str("-".to_string()),
+            Ok(Edn::Symbol(Symbol::new_unchecked("-")))
+        );
+        assert_eq!(read_str("\\o101".to_string()), Ok(Char('A')));
+        assert_eq!(
+            read_str("\"\\u0041\"".to_string()),
+            Ok(Edn::String("A".to_string()))
+        );
+        assert!(matches!(
+            read_str("[".repeat(10_000)).unwrap_err().kind(),
+            ErrorKind::NestingTooDeep(_)
+        ));
     }
-    write!(f, "{end}")
-}
 
-fn sym_split(sym: &str) -> Option<(Option<&str>, &str)> {
-    let caps = symbolPat.captures(sym)?;
-    Some((
-        caps.get(1).map(|x| x.as_str()),
-        caps.get(2).unwrap().as_str(),
-    ))
-}
+    proptest! {
+        #[test]
+        fn test_arbitrary_bytes_do_not_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let _ = read_str(String::from_utf8_lossy(&bytes).into_owned());
+        }
 
-fn skip_whitespace(reader: &mut ReaderIter) {
-    while reader.peek().is_some_and(|&x| is_whitespace(x)) {
-        let _ = reader.next().expect("whitespace does not end reader iter");
+        #[test]
+        fn test_edn_like_input_does_not_panic(s in r#"[-+#^:/\\"{}()\[\]_;, a-zA-Z0-9.NMoux\n]{0,64}"#) {
+            let _ = read_str(s);
+        }
+    }
+
+    #[test]
+    fn test_reader_multiple_forms() {
+        let forms = read_all("1 ; one\n#_ :skipped [2]\n,, {:a 3} #_ 4 ; trailing").unwrap();
+        assert_eq!(
+            forms,
+            vec![
+                Int(1),
+                Edn::Vec(vec![Int(2)]),
+                Map(BTreeMap::from([(
+                    Edn::Keyword(Keyword::new_unchecked("a")),
+                    Int(3)
+                )]))
+            ]
+        );
+        assert_eq!(read_all("  ; nothing here\n"), Ok(vec![]));
+
+        let mut reader = Reader::new("1 (2");
+        assert_eq!(reader.next(), Some(Ok(Int(1))));
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::UnexpectedEof);
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn test_reader_from_buf_read() {
+        let input = "{:name \"λ\"} [1 2]\n#{\\😀}".as_bytes();
+        let forms = Reader::from_reader(std::io::BufReader::with_capacity(1, input))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(forms, read_all("{:name \"λ\"} [1 2]\n#{\\😀}").unwrap());
+
+        let mut reader = Reader::from_reader(&b"1 \"a\xFF\""[..]);
+        assert_eq!(reader.next(), Some(Ok(Int(1))));
+        let err = reader.next().unwrap().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Io(_)));
+        assert_eq!(reader.next(), None);
+
+        // The error while looking for the end of `12` is reported after the form.
+        let mut reader = Reader::from_reader(&b"12\xFF"[..]);
+        assert_eq!(reader.next(), Some(Ok(Int(12))));
+        assert!(matches!(reader.next(), Some(Err(err)) if matches!(err.kind(), ErrorKind::Io(_))));
+        assert_eq!(reader.next(), None);
+
+        for input in [&b"\xFF"[..], &b"[1 \xFF"[..]] {
+            let err = read(&mut ReaderIter::from_reader(input), false, Nil, false).unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::Io(_)), "{err:?}");
+        }
+    }
+
+    #[test]
+    fn test_tag_readers() {
+        let input = "[#myapp/money 12 #js {:a 1} #inst \"1985\"]";
+        let err = read_str(input.to_string()).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ErrorKind::UnknownTag("myapp/money".to_string())
+        );
+        assert_eq!(err.column(), 3);
+
+        let options = ReaderOptions::new()
+            .tag_reader("myapp/money", |edn| match edn {
+                Int(n) => Ok(Edn::BigDecimal(BigDecimal::from(n) / 100)),
+                _ => Err(ErrorKind::InvalidTaggedValue("money must be an int".to_string()).into()),
+            })
+            .keep_unknown_tags();
+        let edn = read_str_with_options(input, options.clone()).unwrap();
+        assert_eq!(
+            edn,
+            Edn::Vec(vec![
+                Edn::BigDecimal(BigDecimal::from_str("0.12").unwrap()),
+                TaggedElement(
+                    "js".to_string(),
+                    Box::new(read_str("{:a 1}".to_string()).unwrap())
+                ),
+                Edn::Inst("1985".parse().unwrap()),
+            ])
+        );
+
+        let err = read_str_with_options("[1 #myapp/money :x]", options).unwrap_err();
+        assert_eq!(err.to_string(), "money must be an int at line 1, column 5");
+    }
+
+    #[test]
+    fn test_read_inst() {
+        let edn = read_str("#inst \"1985-04-12T23:20:50.52Z\"".to_string()).unwrap();
+        assert_eq!(edn.to_string(), "#inst \"1985-04-12T23:20:50.520-00:00\"");
+        assert_eq!(read_str(edn.to_string()), Ok(edn));
+
+        let err = read_str("#inst \"banana\"".to_string()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unrecognized date/time syntax: banana at line 1, column 2"
+        );
+        assert!(read_str("#inst 1985".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_read_uuid() {
+        let edn = read_str("#uuid \"F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6\"".to_string()).unwrap();
+        assert_eq!(
+            edn.to_string(),
+            "#uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\""
+        );
+        assert_eq!(read_str(edn.to_string()), Ok(edn.clone()));
+
+        let map = read_str(format!(
+            "{{{edn} 1 #uuid \"00000000-0000-0000-0000-000000000000\" 2}}"
+        ));
+        let Ok(Map(map)) = map else { panic!() };
+        assert_eq!(map.keys().last(), Some(&edn));
+
+        let err = read_str("#uuid \"f81d4fae\"".to_string()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid UUID string: f81d4fae at line 1, column 2"
+        );
+        assert!(read_str("#uuid :f81d4fae".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_read_meta() {
+        let edn =
+            read_str("^:replace ^{:doc \"paths\" :replace false} [^String x]".to_string()).unwrap();
+        let meta = read_str("{:doc \"paths\" :replace true}".to_string()).unwrap();
+        assert_eq!(
+            edn.meta(),
+            if let Map(meta) = &meta {
+                Some(meta)
+            } else {
+                None
+            }
+        );
+        assert_eq!(edn, Edn::Vec(vec![Edn::Symbol(Symbol::new_unchecked("x"))]));
+        assert_eq!(edn.to_string(), "[x]");
+        assert_eq!(
+            format!("{edn:#}"),
+            "^{:doc \"paths\", :replace true} [^{:tag String} x]"
+        );
+        assert_eq!(read_str(format!("{edn:#}")).unwrap().to_string(), "[x]");
+
+        assert!(read_str("#{^:a x x}".to_string()).is_err());
+        assert!(read_str("^:a 1".to_string()).is_err());
+        assert!(read_str("^1 x".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_symbolic_cache() {
+        let tokens: Vec<String> = (0..SYMBOLIC_CACHE_SIZE + 10)
+            .flat_map(|i| [format!(":k{i}"), format!("s{i}"), ":k0".to_string()])
+            .collect();
+        let mut reader = ReaderIter::new("");
+        for token in &tokens {
+            let edn = reader.interpret_token(token).unwrap();
+            assert_eq!(edn, interpret_token(token).unwrap(), "{token}");
+        }
+        assert!(reader.symbolics.len() <= SYMBOLIC_CACHE_SIZE);
+        assert_eq!(reader.interpret_token("nil"), Some(Nil));
+        assert!(!reader.symbolics.contains_key("nil"));
     }
 }
@@ -0,0 +1,605 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use num::BigInt;
+use serde::ser::{self, Serialize};
+
+use crate::edn_error::SerdeError;
+use crate::edn_reader::Edn;
+use crate::edn_symbol::Keyword;
+use crate::edn_writer::{self, is_valid_tag, PrintOptions};
+
+// Newtype struct names marking values that serde has no type for. This serializer turns
+// them back into the matching `Edn` variant, other serializers see the wrapped value.
+pub(crate) const PRIVATE_PREFIX: &str = "$edn_rs::private::";
+pub(crate) const EDN_TOKEN: &str = "$edn_rs::private::Edn";
+const SET_TOKEN: &str = "$edn_rs::private::Set";
+const LIST_TOKEN: &str = "$edn_rs::private::List";
+const KEYWORD_TOKEN: &str = "$edn_rs::private::Keyword";
+const SYMBOL_TOKEN: &str = "$edn_rs::private::Symbol";
+const BIGINT_TOKEN: &str = "$edn_rs::private::BigInt";
+const BIGDECIMAL_TOKEN: &str = "$edn_rs::private::BigDecimal";
+const BIGRATIONAL_TOKEN: &str = "$edn_rs::private::BigRational";
+const INST_TOKEN: &str = "$edn_rs::private::Inst";
+const UUID_TOKEN: &str = "$edn_rs::private::Uuid";
+const TAGGED_TOKEN: &str = "$edn_rs::private::Tagged";
+const META_TOKEN: &str = "$edn_rs::private::Meta";
+
+/// Serializes `value` as EDN text.
+///
+/// Struct fields and unit variants become keywords, sequences become vectors, `None` becomes
+/// `nil`, and newtype, tuple and struct variants become elements tagged with the variant name.
+/// The text is formatted like `Display for Edn`, so [`crate::from_str`] reads it back.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, SerdeError> {
+    // `to_edn` only builds valid tags, so this does not fail.
+    edn_writer::to_string(&to_edn(value)?, &PrintOptions::default())
+        .map_err(|_| ser::Error::custom("Invalid tag"))
+}
+
+/// Serializes `value` into an [`Edn`] value, with the same mapping as [`to_string`].
+pub fn to_edn<T: Serialize + ?Sized>(value: &T) -> Result<Edn, SerdeError> {
+    value.serialize(Serializer)
+}
+
+/// Writes a collection as an EDN set instead of a vector.
+///
+/// Serde cannot tell sets from other sequences, so set fields need
+/// `#[serde(serialize_with = "edn_rs::edn_ser::as_set")]`. Other formats see the plain sequence.
+pub fn as_set<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(SET_TOKEN, value)
+}
+
+/// The keyword for a field or variant name, which `#[serde(rename)]` can make anything.
+fn keyword(name: &str) -> Result<Edn, SerdeError> {
+    Keyword::new(name)
+        .map(Edn::Keyword)
+        .map_err(ser::Error::custom)
+}
+
+/// Rebuilds the value wrapped in the newtype struct `name` by `Serialize for Edn` or
+/// [`as_set`]. Values wrapped in other newtype structs are returned as they are.
+pub(crate) fn from_private(name: &str, edn: Edn) -> Result<Edn, String> {
+    fn parse<T: FromStr>(name: &str, s: &str) -> Result<T, String> {
+        s.parse()
+            .map_err(|_| format!("Invalid {}: {s}", &name[PRIVATE_PREFIX.len()..]))
+    }
+    Ok(match (name, edn) {
+        (SET_TOKEN, Edn::Vec(vec) | Edn::List(vec)) => Edn::Set(vec.into_iter().collect()),
+        (SET_TOKEN, set @ Edn::Set(_)) => set,
+        (LIST_TOKEN, Edn::Vec(vec) | Edn::List(vec)) => Edn::List(vec),
+        (KEYWORD_TOKEN, Edn::String(s)) => Edn::Keyword(parse(name, &s)?),
+        (SYMBOL_TOKEN, Edn::String(s)) => Edn::Symbol(parse(name, &s)?),
+        (BIGINT_TOKEN, Edn::String(s)) => Edn::BigInt(parse(name, &s)?),
+        (BIGDECIMAL_TOKEN, Edn::String(s)) => Edn::BigDecimal(parse(name, &s)?),
+        (BIGRATIONAL_TOKEN, Edn::String(s)) => Edn::BigRational(parse(name, &s)?),
+        (INST_TOKEN, Edn::String(s)) => Edn::Inst(parse(name, &s)?),
+        (UUID_TOKEN, Edn::String(s)) => Edn::Uuid(parse(name, &s)?),
+        (TAGGED_TOKEN, Edn::Vec(vec)) if matches!(vec.first(), Some(Edn::String(_))) => {
+            match <[Edn; 2]>::try_from(vec) {
+                Ok([Edn::String(tag), edn]) if is_valid_tag(&tag) => {
+                    Edn::TaggedElement(tag, Box::new(edn))
+                }
+                _ => return Err("Invalid tagged element".to_string()),
+            }
+        }
+        (META_TOKEN, Edn::Vec(vec)) if matches!(vec.first(), Some(Edn::Map(_))) => {
+            match <[Edn; 2]>::try_from(vec) {
+                Ok([Edn::Map(meta), edn]) => edn.with_meta(meta),
+                _ => return Err("Invalid metadata".to_string()),
+            }
+        }
+        (name, edn) if name.starts_with(PRIVATE_PREFIX) => {
+            return Err(format!("Invalid value for {name}: {edn}"));
+        }
+        (_, edn) => edn,
+    })
+}
+
+/// Writes the value in the serde data model, so it can be converted to other formats or
+/// cached with any serde serializer.
+///
+/// `nil`, booleans, strings, chars, integers, floats, vectors and maps map to the matching
+/// serde types. The other values are wrapped in newtype structs that this crate's serializer
+/// unwraps again, so `to_edn(&edn)` gives back `edn`. Other formats see the wrapped value:
+///
+/// - keywords and symbols are strings without the leading `:`,
+/// - big integers, big decimals and ratios are strings like `"1/3"`,
+/// - `#inst` and `#uuid` values are strings in their printed form,
+/// - lists and sets are sequences,
+/// - tagged elements are `[tag, value]` pairs and values with metadata `[meta, value]` pairs.
+impl Serialize for Edn {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Edn::Nil => serializer.serialize_unit(),
+            Edn::Bool(b) => serializer.serialize_bool(*b),
+            Edn::String(s) => serializer.serialize_str(s),
+            Edn::Char(c) => serializer.serialize_char(*c),
+            Edn::Symbol(s) => serializer.serialize_newtype_struct(SYMBOL_TOKEN, s.as_str()),
+            Edn::Keyword(s) => serializer.serialize_newtype_struct(KEYWORD_TOKEN, s.as_str()),
+            Edn::Int(n) => serializer.serialize_i64(*n),
+            Edn::BigInt(n) => serializer.serialize_newtype_struct(BIGINT_TOKEN, &n.to_string()),
+            Edn::Float(n) => serializer.serialize_f64(n.into_inner()),
+            Edn::BigDecimal(n) => {
+                serializer.serialize_newtype_struct(BIGDECIMAL_TOKEN, &n.to_string())
+            }
+            Edn::BigRational(n) => {
+                serializer.serialize_newtype_struct(BIGRATIONAL_TOKEN, &n.to_string())
+            }
+            Edn::List(vec) => serializer.serialize_newtype_struct(LIST_TOKEN, vec),
+            Edn::Vec(vec) => serializer.collect_seq(vec),
+            Edn::Set(set) => serializer.serialize_newtype_struct(SET_TOKEN, set),
+            Edn::Map(map) => serializer.collect_map(map),
+            Edn::Inst(inst) => serializer.serialize_newtype_struct(INST_TOKEN, &inst.to_string()),
+            Edn::Uuid(uuid) => serializer.serialize_newtype_struct(UUID_TOKEN, &uuid.to_string()),
+            Edn::TaggedElement(tag, edn) => {
+                serializer.serialize_newtype_struct(TAGGED_TOKEN, &(tag, edn))
+            }
+            Edn::WithMeta(meta, edn) => {
+                serializer.serialize_newtype_struct(META_TOKEN, &(meta, edn))
+            }
+        }
+    }
+}
+
+/// Serializer producing an [`Edn`] value.
+pub struct Serializer;
+
+/// Checks that a variant name, which `#[serde(rename)]` can make anything, reads back as a
+/// tag. Unlike unit variants, `inst` and `uuid` would be read by the built-in readers.
+fn tag(variant: &'static str) -> Result<&'static str, SerdeError> {
+    if is_valid_tag(variant) {
+        Ok(variant)
+    } else {
+        Err(ser::Error::custom(format!("Invalid tag: {variant}")))
+    }
+}
+
+fn tagged(variant: &str, edn: Edn) -> Edn {
+    Edn::TaggedElement(variant.to_string(), Box::new(edn))
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVec;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<Edn, SerdeError> {
+        Ok(Edn::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Edn, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Edn, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Edn, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Edn, SerdeError> {
+        Ok(Edn::Int(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Edn, SerdeError> {
+        Ok(i64::try_from(v).map_or_else(|_| Edn::BigInt(BigInt::from(v)), Edn::Int))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Edn, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Edn, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Edn, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Edn, SerdeError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Edn, SerdeError> {
+        Ok(i64::try_from(v).map_or_else(|_| Edn::BigInt(BigInt::from(v)), Edn::Int))
+    }
+
+    /// Goes through the shortest decimal form like `From<f32> for Edn`, so `1.1f32` is `1.1`.
+    fn serialize_f32(self, v: f32) -> Result<Edn, SerdeError> {
+        Ok(Edn::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Edn, SerdeError> {
+        Ok(Edn::Float(v.into()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Edn, SerdeError> {
+        Ok(Edn::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Edn, SerdeError> {
+        Ok(Edn::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Edn, SerdeError> {
+        Ok(Edn::Vec(v.iter().map(|&b| Edn::Int(b.into())).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Edn, SerdeError> {
+        Ok(Edn::Nil)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Edn, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Edn, SerdeError> {
+        Ok(Edn::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Edn, SerdeError> {
+        Ok(Edn::Nil)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Edn, SerdeError> {
+        keyword(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Edn, SerdeError> {
+        from_private(name, value.serialize(self)?).map_err(ser::Error::custom)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Edn, SerdeError> {
+        let variant = tag(variant)?;
+        Ok(tagged(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, SerdeError> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, SerdeError> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len),
+            variant: Some(tag(variant)?),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, SerdeError> {
+        Ok(SerializeMap {
+            map: BTreeMap::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeMap, SerdeError> {
+        Ok(SerializeMap {
+            map: BTreeMap::new(),
+            key: None,
+            variant: Some(tag(variant)?),
+        })
+    }
+}
+
+pub struct SerializeVec {
+    vec: Vec<Edn>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeVec {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let index = Edn::Int(self.vec.len() as i64);
+        let edn = value
+            .serialize(Serializer)
+            .map_err(|err| err.within(index))?;
+        self.vec.push(edn);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Edn, SerdeError> {
+        let vec = Edn::Vec(self.vec);
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, vec),
+            None => vec,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+pub struct SerializeMap {
+    map: BTreeMap<Edn, Edn>,
+    key: Option<Edn>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeMap {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: Edn, value: &T) -> Result<(), SerdeError> {
+        match value.serialize(Serializer) {
+            Ok(edn) => {
+                self.map.insert(key, edn);
+                Ok(())
+            }
+            Err(err) => Err(err.within(key)),
+        }
+    }
+
+    fn finish(self) -> Result<Edn, SerdeError> {
+        let map = Edn::Map(self.map);
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, map),
+            None => map,
+        })
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key = Some(key.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <SerdeError as ser::Error>::custom("serialize_value before key"))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(keyword(key)?, value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Edn;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(keyword(key)?, value)
+    }
+
+    fn end(self) -> Result<Edn, SerdeError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::edn_de::from_str;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Circle(f64),
+        Rect { w: i32, h: i32 },
+        Point(i32, i32),
+        Empty,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        title: String,
+        #[serde(rename = "doc/id")]
+        id: u64,
+        parent: Option<u64>,
+        #[serde(serialize_with = "as_set")]
+        tags: BTreeSet<String>,
+        shapes: Vec<Shape>,
+        initial: char,
+    }
+
+    #[test]
+    fn test_to_string() {
+        let doc = Doc {
+            title: "say \"hi\"\n".to_string(),
+            id: u64::MAX,
+            parent: None,
+            tags: BTreeSet::from(["a".to_string(), "b".to_string()]),
+            shapes: vec![
+                Shape::Circle(1.5),
+                Shape::Rect { w: 1, h: 2 },
+                Shape::Point(3, 4),
+                Shape::Empty,
+            ],
+            initial: 's',
+        };
+        let s = to_string(&doc).unwrap();
+        assert_eq!(
+            s,
+            "{:doc/id 18446744073709551615N, :initial \\s, :parent nil, \
+             :shapes [#Circle 1.5 #Rect {:h 2, :w 1} #Point [3 4] :Empty], \
+             :tags #{\"a\" \"b\"}, :title \"say \\\"hi\\\"\\n\"}"
+        );
+        assert_eq!(from_str::<Doc>(&s), Ok(doc));
+    }
+
+    #[test]
+    fn test_edn_round_trip() {
+        let text = r#"^:private [nil true "s" \c sym ns/sym :kw :ns/kw 1 2N 1.5 1.5M 1/3
+            (1 2) #{1 2} {:a 1, "b" [2]} #inst "1985-04-12T23:20:50.520-00:00"
+            #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6" #my/tag {:x ^:meta y}]"#;
+        let edn = crate::edn_reader::read_str_with_options(
+            text,
+            crate::ReaderOptions::new().keep_unknown_tags(),
+        )
+        .unwrap();
+        let from_ser = to_edn(&edn).unwrap();
+        assert_eq!(format!("{from_ser:#}"), format!("{edn:#}"));
+        let from_de: Edn = crate::from_edn(edn.clone()).unwrap();
+        assert_eq!(format!("{from_de:#}"), format!("{edn:#}"));
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        #[derive(Serialize)]
+        enum Renamed {
+            #[serde(rename = "inst")]
+            Inst(String),
+            #[serde(rename = "1x")]
+            Digit(i32, i32),
+            #[serde(rename = "a b")]
+            Space { x: i32 },
+        }
+        for value in [
+            Renamed::Inst("x".to_string()),
+            Renamed::Digit(1, 2),
+            Renamed::Space { x: 1 },
+        ] {
+            let err = to_string(&value).unwrap_err();
+            assert!(err.to_string().starts_with("Invalid tag: "), "{err}");
+        }
+        let tagged = Edn::TaggedElement("1x".to_string(), Box::new(Edn::Int(1)));
+        assert!(to_edn(&tagged).is_err());
+    }
+
+    #[test]
+    fn test_f32() {
+        assert_eq!(to_string(&1.1f32), Ok("1.1".to_string()));
+        assert_eq!(
+            to_edn(&[0.1f32, f32::NAN]),
+            Ok(Edn::from(vec![0.1f32, f32::NAN]))
+        );
+        assert_eq!(from_str::<f32>(&to_string(&1.1f32).unwrap()), Ok(1.1));
+    }
+}
@@ -0,0 +1,195 @@
+//! Values annotated with where they were read from, see
+//! [`crate::edn_reader::read_spanned`].
+
+use std::collections::BTreeMap;
+
+use crate::edn_error::Position;
+use crate::edn_reader::{qualify_key, Edn};
+
+/// The source text of a form, from its first char up to the position after its last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A value read with [`crate::edn_reader::read_spanned`], with the span of every form in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedEdn {
+    pub span: Span,
+    pub value: SpannedValue,
+}
+
+/// The value of a [`SpannedEdn`]. Collections hold their elements in source order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedValue {
+    /// A value without nested forms, including the results of tag readers like `#inst`.
+    Scalar(Edn),
+    List(Vec<SpannedEdn>),
+    Vec(Vec<SpannedEdn>),
+    Set(Vec<SpannedEdn>),
+    /// The entries of a map. Keys of namespace maps are qualified like in the read value.
+    Map(Vec<(SpannedEdn, SpannedEdn)>),
+    TaggedElement(String, Box<SpannedEdn>),
+    WithMeta(BTreeMap<Edn, Edn>, Box<SpannedEdn>),
+}
+
+/// The syntax of a form, which decides how the spans of the forms read inside it map onto
+/// the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shape {
+    Scalar,
+    List,
+    Vec,
+    Set,
+    Map,
+    NamespaceMap,
+    Tagged,
+    Meta,
+}
+
+impl Shape {
+    /// The shape of the form read by the reader macro for `ch`, followed by `next`.
+    pub(crate) fn of_macro(ch: char, next: Option<char>) -> Shape {
+        match (ch, next) {
+            ('(', _) => Shape::List,
+            ('[', _) => Shape::Vec,
+            ('{', _) => Shape::Map,
+            ('^', _) | ('#', Some('^')) => Shape::Meta,
+            ('#', Some('{')) => Shape::Set,
+            ('#', Some(':')) => Shape::NamespaceMap,
+            ('#', Some(ch)) if ch.is_alphabetic() => Shape::Tagged,
+            _ => Shape::Scalar,
+        }
+    }
+}
+
+impl SpannedEdn {
+    /// Pairs `edn`, read from `span`, with the spanned forms read inside it in source order.
+    pub(crate) fn new(edn: &Edn, shape: Shape, children: Vec<SpannedEdn>, span: Span) -> Self {
+        let value = match (shape, edn) {
+            (Shape::List, _) => SpannedValue::List(children),
+            (Shape::Vec, _) => SpannedValue::Vec(children),
+            (Shape::Set, _) => SpannedValue::Set(children),
+            (Shape::Map, _) => SpannedValue::Map(pairs(children, None)),
+            (Shape::NamespaceMap, _) => {
+                let mut children = children.into_iter();
+                let namespace = match children.next().map(|ns| ns.value) {
+                    Some(SpannedValue::Scalar(Edn::Symbol(ns))) => ns.as_str().to_string(),
+                    _ => unreachable!("namespace maps start with a symbol"),
+                };
+                SpannedValue::Map(pairs(children.collect(), Some(&namespace)))
+            }
+            (Shape::Meta, Edn::WithMeta(meta, _)) => {
+                let target = children.into_iter().last().expect("metadata has a target");
+                SpannedValue::WithMeta(meta.clone(), Box::new(target))
+            }
+            // Tag readers can return anything, only unknown tags kept as they are have the
+            // element in the value.
+            (Shape::Tagged, Edn::TaggedElement(tag, inner)) => match children.into_iter().last() {
+                Some(element) if element.to_edn() == **inner => {
+                    SpannedValue::TaggedElement(tag.clone(), Box::new(element))
+                }
+                _ => SpannedValue::Scalar(edn.clone()),
+            },
+            _ => SpannedValue::Scalar(edn.clone()),
+        };
+        SpannedEdn { span, value }
+    }
+
+    /// The value without the spans, equal to what [`crate::edn_reader::read_str`] returns.
+    pub fn to_edn(&self) -> Edn {
+        fn all<C: FromIterator<Edn>>(items: &[SpannedEdn]) -> C {
+            items.iter().map(SpannedEdn::to_edn).collect()
+        }
+        match &self.value {
+            SpannedValue::Scalar(edn) => edn.clone(),
+            SpannedValue::List(items) => Edn::List(all(items)),
+            SpannedValue::Vec(items) => Edn::Vec(all(items)),
+            SpannedValue::Set(items) => Edn::Set(all(items)),
+            SpannedValue::Map(entries) => Edn::Map(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.to_edn(), v.to_edn()))
+                    .collect(),
+            ),
+            SpannedValue::TaggedElement(tag, element) => {
+                Edn::TaggedElement(tag.clone(), Box::new(element.to_edn()))
+            }
+            SpannedValue::WithMeta(meta, target) => target.to_edn().with_meta(meta.clone()),
+        }
+    }
+}
+
+fn pairs(children: Vec<SpannedEdn>, namespace: Option<&str>) -> Vec<(SpannedEdn, SpannedEdn)> {
+    let mut children = children.into_iter();
+    let mut pairs = Vec::new();
+    while let (Some(mut k), Some(v)) = (children.next(), children.next()) {
+        if let (Some(namespace), SpannedValue::Scalar(key)) = (namespace, &k.value) {
+            k.value = SpannedValue::Scalar(qualify_key(namespace, key.clone()));
+        }
+        pairs.push((k, v));
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::{
+        read_spanned, read_spanned_with_options, read_str, read_str_with_options, ReaderOptions,
+    };
+
+    fn at(span: Span) -> ((usize, usize), (usize, usize)) {
+        (
+            (span.start.line, span.start.column),
+            (span.end.line, span.end.column),
+        )
+    }
+
+    #[test]
+    fn test_spans() {
+        let source = "{:port 80\n :hosts #{\"a\" ; main\n         \"b\"}}";
+        let spanned = read_spanned(source).unwrap();
+        assert_eq!(at(spanned.span), ((1, 1), (3, 15)));
+        assert_eq!(spanned.span.end.offset, source.len());
+        let SpannedValue::Map(entries) = &spanned.value else {
+            panic!("{spanned:?}")
+        };
+        let (port, port_value) = &entries[0];
+        assert_eq!(
+            port.value,
+            SpannedValue::Scalar(read_str(":port".into()).unwrap())
+        );
+        assert_eq!(at(port.span), ((1, 2), (1, 7)));
+        assert_eq!(at(port_value.span), ((1, 8), (1, 10)));
+        let SpannedValue::Set(hosts) = &entries[1].1.value else {
+            panic!("{spanned:?}")
+        };
+        assert_eq!(at(hosts[1].span), ((3, 10), (3, 13)));
+    }
+
+    #[test]
+    fn test_to_edn() {
+        for source in [
+            "(1 [2.5M \\a] #{:x} {\"k\" nil})",
+            "^:private ^{:tag T} [#_ ignored x]",
+            "#:ns{:a 1, :_/b 2, c/d 3, e 4}",
+            "[#inst \"2024-01-01\" ##Inf #my/tag {:a 1}]",
+        ] {
+            let options = || ReaderOptions::new().keep_unknown_tags();
+            let spanned = read_spanned_with_options(source, options()).unwrap();
+            let edn = read_str_with_options(source, options()).unwrap();
+            assert_eq!(spanned.to_edn(), edn);
+            assert_eq!(spanned.to_edn().meta(), edn.meta());
+        }
+        let spanned = read_spanned("[^:m #_ 1 x ##NaN]").unwrap();
+        let SpannedValue::Vec(items) = &spanned.value else {
+            panic!("{spanned:?}")
+        };
+        assert!(
+            matches!(&items[0].value, SpannedValue::WithMeta(_, x) if at(x.span) == ((1, 11), (1, 12)))
+        );
+        assert_eq!(at(items[1].span), ((1, 13), (1, 18)));
+    }
+}
@@ -0,0 +1,420 @@
+//! Streaming EDN output to an [`io::Write`].
+//!
+//! [`EdnWriter`] prints values straight to the output without building strings, and has a
+//! push API to emit collections piece by piece without materialising an [`Edn`] tree:
+//!
+//! ```
+//! use edn_rs::edn_stream::EdnWriter;
+//! use edn_rs::Edn;
+//!
+//! let mut writer = EdnWriter::new(Vec::new());
+//! writer.begin_map().unwrap();
+//! writer.key(&Edn::Keyword("ids".parse().unwrap())).unwrap();
+//! writer.begin_vec().unwrap();
+//! for id in 1..4 {
+//!     writer.value(&Edn::Int(id)).unwrap();
+//! }
+//! writer.end_vec().unwrap();
+//! writer.end_map().unwrap();
+//! assert_eq!(writer.finish().unwrap(), b"{:ids [1 2 3]}\n");
+//! ```
+
+use core::fmt;
+use std::error::Error;
+use std::io;
+
+use crate::edn_reader::Edn;
+use crate::edn_writer::{is_tag, PrintOptions, Writer};
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    /// `key` was called outside a map.
+    KeyOutsideMap,
+    /// A value or collection was written where a map key was expected.
+    ExpectedKey,
+    /// A key or the end of a collection came after a map key or tag that had no value yet.
+    ExpectedValue,
+    /// The collection being ended is not the innermost open one.
+    Unbalanced,
+    /// `finish` was called with collections still open.
+    Unfinished(usize),
+    /// A tag that does not read as one, like `1x` or `a b`.
+    InvalidTag(String),
+    /// A value with a tag that would not read back, see [`Writer::write`]. The part of the
+    /// value before the tag has been written.
+    InvalidValue,
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "{err}"),
+            StreamError::KeyOutsideMap => write!(f, "Map key written outside a map"),
+            StreamError::ExpectedKey => write!(f, "Expected a map key"),
+            StreamError::ExpectedValue => write!(f, "Expected a value"),
+            StreamError::Unbalanced => {
+                write!(f, "Ended a collection that is not the innermost open one")
+            }
+            StreamError::Unfinished(open) => write!(f, "{open} collections left open"),
+            StreamError::InvalidTag(tag) => write!(f, "Invalid tag: {tag}"),
+            StreamError::InvalidValue => write!(f, "Value with an invalid tag"),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        StreamError::Io(err)
+    }
+}
+
+/// Adapts an [`io::Write`] to the [`fmt::Write`] that [`Writer`] prints to, keeping the io
+/// error that `fmt::Error` cannot carry.
+struct IoAdapter<'w, W> {
+    out: &'w mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    List,
+    Vec,
+    Set,
+    Map,
+}
+
+impl Kind {
+    fn delimiters(self) -> (&'static str, &'static str) {
+        match self {
+            Kind::List => ("(", ")"),
+            Kind::Vec => ("[", "]"),
+            Kind::Set => ("#{", "}"),
+            Kind::Map => ("{", "}"),
+        }
+    }
+}
+
+struct Frame {
+    kind: Kind,
+    count: usize,
+    /// Whether a map key was written and its value has not been.
+    awaiting_value: bool,
+}
+
+/// Writes EDN to an [`io::Write`] as it is produced.
+///
+/// Each top level value is followed by a newline. Output goes to `out` in small pieces, so
+/// wrap files and sockets in an [`io::BufWriter`].
+///
+/// [`PrintOptions::print_length`] and [`PrintOptions::print_level`] only truncate inside the
+/// values passed to [`EdnWriter::value`], and maps built with [`EdnWriter::begin_map`] are
+/// never printed as namespace maps since their keys are not known up front.
+pub struct EdnWriter<W> {
+    out: W,
+    options: PrintOptions,
+    stack: Vec<Frame>,
+    /// Whether a tag was written and its element has not been.
+    tagged: bool,
+}
+
+impl<W: io::Write> EdnWriter<W> {
+    pub fn new(out: W) -> Self {
+        EdnWriter {
+            out,
+            options: PrintOptions::default(),
+            stack: Vec::new(),
+            tagged: false,
+        }
+    }
+
+    pub fn with_options(mut self, options: PrintOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes a complete value, or a map value if a key was just written.
+    pub fn value(&mut self, edn: &Edn) -> Result<(), StreamError> {
+        self.before(false)?;
+        self.print(edn)?;
+        self.after(false)
+    }
+
+    /// Writes the key of the next map entry, to be followed by its value.
+    pub fn key(&mut self, edn: &Edn) -> Result<(), StreamError> {
+        self.before(true)?;
+        self.print(edn)?;
+        self.after(true)
+    }
+
+    /// Writes `#tag `, which the next value or collection completes.
+    pub fn tag(&mut self, tag: &str) -> Result<(), StreamError> {
+        if !is_tag(tag) {
+            return Err(StreamError::InvalidTag(tag.to_string()));
+        }
+        self.before(false)?;
+        write!(self.out, "#{tag} ")?;
+        self.tagged = true;
+        Ok(())
+    }
+
+    pub fn begin_list(&mut self) -> Result<(), StreamError> {
+        self.begin(Kind::List)
+    }
+
+    pub fn end_list(&mut self) -> Result<(), StreamError> {
+        self.end(Kind::List)
+    }
+
+    pub fn begin_vec(&mut self) -> Result<(), StreamError> {
+        self.begin(Kind::Vec)
+    }
+
+    pub fn end_vec(&mut self) -> Result<(), StreamError> {
+        self.end(Kind::Vec)
+    }
+
+    pub fn begin_set(&mut self) -> Result<(), StreamError> {
+        self.begin(Kind::Set)
+    }
+
+    pub fn end_set(&mut self) -> Result<(), StreamError> {
+        self.end(Kind::Set)
+    }
+
+    pub fn begin_map(&mut self) -> Result<(), StreamError> {
+        self.begin(Kind::Map)
+    }
+
+    pub fn end_map(&mut self) -> Result<(), StreamError> {
+        self.end(Kind::Map)
+    }
+
+    /// Flushes the output and returns it, failing if collections are still open.
+    pub fn finish(mut self) -> Result<W, StreamError> {
+        if !self.stack.is_empty() || self.tagged {
+            return Err(StreamError::Unfinished(self.stack.len()));
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn print(&mut self, edn: &Edn) -> Result<(), StreamError> {
+        let mut adapter = IoAdapter {
+            out: &mut self.out,
+            error: None,
+        };
+        let result = Writer::new(&mut adapter)
+            .with_options(self.options)
+            .with_level(self.stack.len())
+            .write(edn);
+        match (result, adapter.error) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(err)) => Err(StreamError::Io(err)),
+            // The writer itself only fails on tags.
+            (Err(_), None) => Err(StreamError::InvalidValue),
+        }
+    }
+
+    /// Checks that a key or value may come next and writes the separator before it.
+    fn before(&mut self, key: bool) -> Result<(), StreamError> {
+        if self.tagged {
+            if key {
+                return Err(StreamError::ExpectedValue);
+            }
+            self.tagged = false;
+            return Ok(());
+        }
+        let map_commas = self.options.map_commas;
+        let Some(frame) = self.stack.last() else {
+            return match key {
+                true => Err(StreamError::KeyOutsideMap),
+                false => Ok(()),
+            };
+        };
+        let separator = match (frame.kind, key) {
+            (Kind::Map, true) if frame.awaiting_value => return Err(StreamError::ExpectedValue),
+            (Kind::Map, false) if !frame.awaiting_value => return Err(StreamError::ExpectedKey),
+            (Kind::Map, true) if frame.count > 0 && map_commas => ", ",
+            (Kind::Map, _) => " ",
+            (_, true) => return Err(StreamError::KeyOutsideMap),
+            _ => " ",
+        };
+        if frame.count > 0 || frame.awaiting_value {
+            self.out.write_all(separator.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn after(&mut self, key: bool) -> Result<(), StreamError> {
+        match self.stack.last_mut() {
+            Some(frame) if key => frame.awaiting_value = true,
+            Some(frame) => {
+                frame.awaiting_value = false;
+                frame.count += 1;
+            }
+            None => self.out.write_all(b"\n")?,
+        }
+        Ok(())
+    }
+
+    fn begin(&mut self, kind: Kind) -> Result<(), StreamError> {
+        self.before(false)?;
+        self.out.write_all(kind.delimiters().0.as_bytes())?;
+        self.stack.push(Frame {
+            kind,
+            count: 0,
+            awaiting_value: false,
+        });
+        Ok(())
+    }
+
+    fn end(&mut self, kind: Kind) -> Result<(), StreamError> {
+        match self.stack.last() {
+            Some(frame) if frame.kind != kind => return Err(StreamError::Unbalanced),
+            Some(frame) if frame.awaiting_value || self.tagged => {
+                return Err(StreamError::ExpectedValue)
+            }
+            Some(_) => {}
+            None => return Err(StreamError::Unbalanced),
+        }
+        self.stack.pop();
+        self.out.write_all(kind.delimiters().1.as_bytes())?;
+        self.after(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::{read_str_with_options, ReaderOptions};
+
+    fn kw(s: &str) -> Edn {
+        Edn::Keyword(s.parse().unwrap())
+    }
+
+    fn output(writer: EdnWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_values_match_display() {
+        let text = r#"{:a [1 2.0 "s" \c], :b #{nil true}, :c (#my/tag x 1/2)}"#;
+        let edn = read_str_with_options(text, ReaderOptions::new().keep_unknown_tags()).unwrap();
+        let mut writer = EdnWriter::new(Vec::new());
+        writer.value(&edn).unwrap();
+        writer.value(&Edn::Int(1)).unwrap();
+        assert_eq!(output(writer), format!("{edn}\n1\n"));
+    }
+
+    #[test]
+    fn test_push_api() {
+        let mut writer = EdnWriter::new(Vec::new());
+        writer.begin_map().unwrap();
+        writer.key(&kw("a")).unwrap();
+        writer.begin_list().unwrap();
+        writer.value(&Edn::Int(1)).unwrap();
+        writer.tag("inst").unwrap();
+        writer
+            .value(&Edn::String("2024-01-01".to_string()))
+            .unwrap();
+        writer.end_list().unwrap();
+        writer.key(&kw("b")).unwrap();
+        writer.begin_set().unwrap();
+        writer.end_set().unwrap();
+        writer.key(&kw("c")).unwrap();
+        writer.tag("my/tag").unwrap();
+        writer.begin_vec().unwrap();
+        writer.value(&Edn::Nil).unwrap();
+        writer.end_vec().unwrap();
+        writer.end_map().unwrap();
+        assert_eq!(
+            output(writer),
+            "{:a (1 #inst \"2024-01-01\"), :b #{}, :c #my/tag [nil]}\n"
+        );
+
+        let mut writer =
+            EdnWriter::new(Vec::new()).with_options(PrintOptions::new().map_commas(false));
+        writer.begin_map().unwrap();
+        writer.key(&kw("a")).unwrap();
+        writer.value(&Edn::Int(1)).unwrap();
+        writer.key(&kw("b")).unwrap();
+        writer.value(&Edn::Int(2)).unwrap();
+        writer.end_map().unwrap();
+        assert_eq!(output(writer), "{:a 1 :b 2}\n");
+    }
+
+    #[test]
+    fn test_misuse() {
+        let mut writer = EdnWriter::new(Vec::new());
+        assert!(matches!(
+            writer.key(&kw("a")),
+            Err(StreamError::KeyOutsideMap)
+        ));
+        writer.begin_map().unwrap();
+        assert!(matches!(
+            writer.value(&Edn::Nil),
+            Err(StreamError::ExpectedKey)
+        ));
+        writer.key(&kw("a")).unwrap();
+        assert!(matches!(
+            writer.key(&kw("b")),
+            Err(StreamError::ExpectedValue)
+        ));
+        assert!(matches!(writer.end_map(), Err(StreamError::ExpectedValue)));
+        assert!(matches!(writer.end_vec(), Err(StreamError::Unbalanced)));
+        assert!(matches!(writer.finish(), Err(StreamError::Unfinished(1))));
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        let mut writer = EdnWriter::new(Vec::new());
+        for tag in ["", "1x", "a b", "#a", ":a"] {
+            assert!(matches!(writer.tag(tag), Err(StreamError::InvalidTag(t)) if t == tag));
+        }
+        let tagged = Edn::TaggedElement("1x".to_string(), Box::new(Edn::Nil));
+        assert!(matches!(
+            writer.value(&tagged),
+            Err(StreamError::InvalidValue)
+        ));
+    }
+
+    #[test]
+    fn test_io_errors() {
+        struct Failing;
+
+        impl io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = EdnWriter::new(Failing);
+        match writer.value(&Edn::Vec(vec![Edn::Int(1)])) {
+            Err(StreamError::Io(err)) => assert_eq!(err.to_string(), "disk full"),
+            other => panic!("{other:?}"),
+        }
+    }
+}
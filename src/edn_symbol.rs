@@ -0,0 +1,280 @@
+use core::fmt;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use lazy_static::lazy_static;
+
+use crate::edn_reader::{read_symbolic, Edn};
+
+lazy_static! {
+    /// The text of every live keyword and symbol, see [`Interned`].
+    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner::default());
+}
+
+#[derive(Default)]
+struct Interner {
+    texts: HashSet<Arc<str>>,
+    /// Texts only the interner refers to are dropped once it grows past this size.
+    collect_at: usize,
+}
+
+/// Text shared by every equal keyword and symbol, so that comparing them compares pointers
+/// and hashing them hashes a pointer.
+#[derive(Clone)]
+struct Interned(Arc<str>);
+
+impl Interned {
+    fn new(text: &str) -> Self {
+        let mut interner = INTERNER.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(text) = interner.texts.get(text) {
+            return Interned(text.clone());
+        }
+        if interner.texts.len() >= interner.collect_at {
+            interner.texts.retain(|text| Arc::strong_count(text) > 1);
+            interner.collect_at = (interner.texts.len() * 2).max(1024);
+        }
+        let text = Arc::<str>::from(text);
+        interner.texts.insert(text.clone());
+        Interned(text)
+    }
+
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Interned {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Interned {}
+
+impl PartialOrd for Interned {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interned {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other {
+            Ordering::Equal
+        } else {
+            self.0.cmp(&other.0)
+        }
+    }
+}
+
+impl Hash for Interned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state);
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+/// A symbol like `inc` or `clojure.core/inc`, as read by [`crate::edn_reader`].
+///
+/// Symbols are interned: equal symbols share their text, so cloning, comparing and hashing
+/// them does not touch it. Ordering compares the full text, so namespaced symbols sort with
+/// their namespace.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(Interned);
+
+/// A keyword like `:id` or `:user/id`, interned like [`Symbol`]. The text does not include
+/// the leading `:`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Keyword(Interned);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSymbolError(String);
+
+impl fmt::Display for ParseSymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid symbol or keyword: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSymbolError {}
+
+/// Splits at the first `/`, like `clojure.core/symbol`. `/` on its own is a name.
+fn split(text: &str) -> (Option<&str>, &str) {
+    match text.split_once('/') {
+        Some((namespace, name)) if !namespace.is_empty() => (Some(namespace), name),
+        _ => (None, text),
+    }
+}
+
+impl Symbol {
+    /// Creates the symbol the reader reads from `text`, if it reads as one.
+    pub fn new(text: &str) -> Result<Self, ParseSymbolError> {
+        match read_symbolic(text) {
+            Some(Edn::Symbol(symbol)) => Ok(symbol),
+            _ => Err(ParseSymbolError(text.to_string())),
+        }
+    }
+
+    pub fn namespaced(namespace: &str, name: &str) -> Result<Self, ParseSymbolError> {
+        Self::new(&format!("{namespace}/{name}"))
+    }
+
+    /// Wraps text the reader has already accepted as a symbol.
+    pub(crate) fn new_unchecked(text: &str) -> Self {
+        Symbol(Interned::new(text))
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        split(self.0.as_str()).0
+    }
+
+    pub fn name(&self) -> &str {
+        split(self.0.as_str()).1
+    }
+
+    /// The full text, including the namespace.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl Keyword {
+    /// Creates the keyword the reader reads from `:` followed by `text`, if it reads as one.
+    pub fn new(text: &str) -> Result<Self, ParseSymbolError> {
+        match read_symbolic(&format!(":{text}")) {
+            Some(Edn::Keyword(keyword)) => Ok(keyword),
+            _ => Err(ParseSymbolError(text.to_string())),
+        }
+    }
+
+    pub fn namespaced(namespace: &str, name: &str) -> Result<Self, ParseSymbolError> {
+        Self::new(&format!("{namespace}/{name}"))
+    }
+
+    /// Wraps text without the `:` that the reader has already accepted as a keyword.
+    pub(crate) fn new_unchecked(text: &str) -> Self {
+        Keyword(Interned::new(text))
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        split(self.0.as_str()).0
+    }
+
+    pub fn name(&self) -> &str {
+        split(self.0.as_str()).1
+    }
+
+    /// The full text without the leading `:`, including the namespace.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, ":{}", self.0.as_str())
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = ParseSymbolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl FromStr for Keyword {
+    type Err = ParseSymbolError;
+
+    /// Parses a keyword with or without its leading `:`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s.strip_prefix(':').unwrap_or(s))
+    }
+}
+
+impl TryFrom<&str> for Symbol {
+    type Error = ParseSymbolError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&str> for Keyword {
+    type Error = ParseSymbolError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespace_and_name() {
+        let kw: Keyword = ":user/id".parse().unwrap();
+        assert_eq!((kw.namespace(), kw.name()), (Some("user"), "id"));
+        assert_eq!(kw.to_string(), ":user/id");
+        assert_eq!(Keyword::try_from("id").unwrap().namespace(), None);
+        assert_eq!(Keyword::namespaced("user", "id"), Ok(kw));
+
+        let sym = Symbol::new("clojure.core//").unwrap();
+        assert_eq!((sym.namespace(), sym.name()), (Some("clojure.core"), "/"));
+        let sym = Symbol::new("/").unwrap();
+        assert_eq!((sym.namespace(), sym.name()), (None, "/"));
+        assert_eq!(sym.to_string(), "/");
+    }
+
+    #[test]
+    fn test_interning() {
+        let shares = |a: &Keyword, b: &Keyword| Arc::ptr_eq(&a.0 .0, &b.0 .0);
+        let read = |s: &str| match crate::edn_reader::read_str(s.to_string()).unwrap() {
+            Edn::Keyword(kw) => kw,
+            edn => panic!("{edn:?}"),
+        };
+        let kw = Keyword::new("db/id").unwrap();
+        assert!(shares(&kw, &read(":db/id")));
+        assert!(shares(&kw, &":db/id".parse().unwrap()));
+        assert_ne!(kw, read(":db/ident"));
+        assert_eq!(Symbol::new("db/id").unwrap().as_str(), kw.as_str());
+
+        // Collecting unused texts keeps live values equal to new ones.
+        for i in 0..5000 {
+            Keyword::new(&format!("k{i}")).unwrap();
+        }
+        assert!(shares(&kw, &Keyword::new("db/id").unwrap()));
+        assert_eq!(Keyword::new("k1").unwrap(), read(":k1"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for s in [
+            "", "nil", "true", "1a", "-1", "a b", "a;b", "a{", "#a", "::a", "a:", "ns:/a", "@a",
+            ":a", "\"a\"", "/a",
+        ] {
+            assert!(Symbol::new(s).is_err(), "{s}");
+        }
+        for s in ["", "::a", "a:", "1 2", "a/"] {
+            assert!(s.parse::<Keyword>().is_err(), "{s}");
+        }
+        assert!(Symbol::new("-").is_ok());
+        assert!(Keyword::new("nil").is_ok());
+    }
+}
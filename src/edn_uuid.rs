@@ -0,0 +1,98 @@
+use core::fmt;
+use std::str::FromStr;
+
+/// A 128-bit UUID read from a `#uuid` literal.
+///
+/// Ordering and equality compare the bytes, so `#uuid` values work as map and set keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Uuid(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseUuidError(String);
+
+impl fmt::Display for ParseUuidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid UUID string: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseUuidError {}
+
+/// Group lengths in hex digits of the canonical `8-4-4-4-12` form.
+const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+
+impl FromStr for Uuid {
+    type Err = ParseUuidError;
+
+    /// Parses the canonical hyphenated form. Hex digits may be upper or lower case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseUuidError(s.to_string());
+        let groups = s.split('-').collect::<Vec<_>>();
+        if groups.len() != GROUPS.len() || groups.iter().zip(GROUPS).any(|(g, len)| g.len() != len)
+        {
+            return Err(err());
+        }
+        let digits = groups.concat();
+        // Checked before slicing, group lengths are in bytes and could split a char.
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+        let mut bytes = [0; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).map_err(|_| err())?;
+        }
+        Ok(Uuid(bytes))
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = self.0.iter();
+        for (i, len) in GROUPS.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, "-")?;
+            }
+            for byte in bytes.by_ref().take(len / 2) {
+                write!(f, "{byte:02x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_print() {
+        let uuid: Uuid = "F81D4FAE-7dec-11d0-a765-00a0c91e6bf6".parse().unwrap();
+        assert_eq!(uuid.as_bytes()[..4], [0xf8, 0x1d, 0x4f, 0xae]);
+        assert_eq!(uuid.to_string(), "f81d4fae-7dec-11d0-a765-00a0c91e6bf6");
+
+        for s in [
+            "",
+            "f81d4fae7dec11d0a76500a0c91e6bf6",
+            "f81d4fae-7dec-11d0-a765-00a0c91e6bf",
+            "f81d4fae-7dec-11d0-a765-00a0c91e6bf6a",
+            "f81d4fae-7dec-11d0a-765-00a0c91e6bf6",
+            "f81d4fae-7dec-11d0-a765-00a0c91e6bg6",
+            "+81d4fae-7dec-11d0-a765-00a0c91e6bf6",
+            "{f81d4fae-7dec-11d0-a765-00a0c91e6bf6}",
+            "f81d4fae-7dec-11d0-a765-00a0c91e6bλ",
+            "f81d4fae-7dec-11d0-a765-00a0c91e6λb",
+        ] {
+            assert!(s.parse::<Uuid>().is_err(), "{s}");
+        }
+    }
+}
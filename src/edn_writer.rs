@@ -0,0 +1,674 @@
+use core::fmt::{self, Write};
+use std::collections::BTreeMap;
+
+use bigdecimal::BigDecimal;
+use num::Signed;
+use sha2::{Digest, Sha256};
+
+use crate::edn_reader::{builtin_tag_reader, read_symbolic, Edn};
+
+/// Options for printing EDN, mirroring Clojure's `*print-...*` vars.
+///
+/// Output is readable by [`crate::edn_reader::read_str`] and reads back as the printed value,
+/// unless it was cut short with [`PrintOptions::print_length`] or [`PrintOptions::print_level`].
+/// Values with tags that do not read back are not printed, see [`Writer::write`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintOptions {
+    named_chars: bool,
+    pub(crate) map_commas: bool,
+    namespace_maps: bool,
+    print_length: Option<usize>,
+    print_level: Option<usize>,
+    ascii_only: bool,
+    print_meta: bool,
+    canonical: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            named_chars: true,
+            map_commas: true,
+            namespace_maps: false,
+            print_length: None,
+            print_level: None,
+            ascii_only: false,
+            print_meta: false,
+            canonical: false,
+        }
+    }
+}
+
+impl PrintOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options for canonical output, where equal values print as identical text.
+    ///
+    /// Map entries and set elements come in the order of [`Edn`]'s `Ord`, entries are separated
+    /// by single spaces, metadata is left out, `-0.0` prints as `0.0`, big decimals print
+    /// without trailing zeros and only use an exponent beyond 20 zeros, and escapes and
+    /// `#inst`/`#uuid` renderings are the fixed ones of the default options. Changing options
+    /// afterwards gives up canonicity.
+    pub fn canonical() -> Self {
+        PrintOptions {
+            map_commas: false,
+            canonical: true,
+            ..Self::default()
+        }
+    }
+
+    /// Prints `\newline`, `\space`, `\tab`, `\backspace`, `\formfeed` and `\return` by name
+    /// instead of as `\uXXXX`. On by default.
+    pub fn named_chars(mut self, named_chars: bool) -> Self {
+        self.named_chars = named_chars;
+        self
+    }
+
+    /// Separates map entries with `", "` like Clojure, or with a single space. On by default.
+    pub fn map_commas(mut self, map_commas: bool) -> Self {
+        self.map_commas = map_commas;
+        self
+    }
+
+    /// Prints maps whose keys all share a namespace as `#:ns{:a 1}`, like
+    /// `*print-namespace-maps*`.
+    pub fn namespace_maps(mut self, namespace_maps: bool) -> Self {
+        self.namespace_maps = namespace_maps;
+        self
+    }
+
+    /// Prints at most `length` elements of each collection followed by `...`, like
+    /// `*print-length*`.
+    pub fn print_length(mut self, length: usize) -> Self {
+        self.print_length = Some(length);
+        self
+    }
+
+    /// Prints collections nested `level` deep as `#`, like `*print-level*`.
+    pub fn print_level(mut self, level: usize) -> Self {
+        self.print_level = Some(level);
+        self
+    }
+
+    /// Escapes all non-ASCII chars in strings and chars as `\uXXXX`. Chars outside the basic
+    /// multilingual plane have no escape and are still printed as they are.
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Prints metadata as `^{...}` before the value, like `*print-meta*`.
+    pub fn print_meta(mut self, print_meta: bool) -> Self {
+        self.print_meta = print_meta;
+        self
+    }
+}
+
+/// Prints EDN values as text to a [`fmt::Write`].
+pub struct Writer<W> {
+    out: W,
+    options: PrintOptions,
+    level: usize,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W) -> Self {
+        Writer {
+            out,
+            options: PrintOptions::default(),
+            level: 0,
+        }
+    }
+
+    pub fn with_options(mut self, options: PrintOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Starts at nesting `level`, for values written inside collections printed elsewhere.
+    pub(crate) fn with_level(mut self, level: usize) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Prints `edn`, failing with [`fmt::Error`] if `out` fails or on a tagged element whose
+    /// tag would not read back, like `1x`, `a b` or `inst`.
+    pub fn write(&mut self, edn: &Edn) -> fmt::Result {
+        match edn {
+            Edn::Nil => self.out.write_str("nil"),
+            Edn::Bool(b) => write!(self.out, "{b}"),
+            Edn::String(s) => self.write_string(s),
+            Edn::Char(c) => self.write_char(*c),
+            Edn::Symbol(s) => write!(self.out, "{s}"),
+            Edn::Keyword(k) => write!(self.out, "{k}"),
+            Edn::Int(n) => write!(self.out, "{n}"),
+            Edn::BigInt(n) => write!(self.out, "{n}N"),
+            Edn::Float(n) => self.write_float(n.into_inner()),
+            Edn::BigDecimal(n) if self.options.canonical => self.write_canonical_decimal(n),
+            Edn::BigDecimal(n) => write!(self.out, "{n}M"),
+            Edn::BigRational(n) => write!(self.out, "{}/{}", n.numer(), n.denom()),
+            Edn::List(vec) => self.write_seq("(", vec, ")"),
+            Edn::Vec(vec) => self.write_seq("[", vec, "]"),
+            Edn::Set(set) => self.write_seq("#{", set, "}"),
+            Edn::Map(map) => self.write_map(map),
+            Edn::Inst(inst) => write!(self.out, "#inst \"{inst}\""),
+            Edn::Uuid(uuid) => write!(self.out, "#uuid \"{uuid}\""),
+            Edn::TaggedElement(tag, _) if !is_valid_tag(tag) => Err(fmt::Error),
+            Edn::TaggedElement(tag, edn) => {
+                write!(self.out, "#{tag} ")?;
+                self.write(edn)
+            }
+            Edn::WithMeta(meta, edn) if self.options.print_meta => {
+                self.out.write_char('^')?;
+                self.write_map(meta)?;
+                self.out.write_char(' ')?;
+                self.write(edn)
+            }
+            Edn::WithMeta(_, edn) => self.write(edn),
+        }
+    }
+
+    fn write_string(&mut self, s: &str) -> fmt::Result {
+        self.out.write_char('"')?;
+        for c in s.chars() {
+            match c {
+                '"' => self.out.write_str("\\\"")?,
+                '\\' => self.out.write_str("\\\\")?,
+                '\n' => self.out.write_str("\\n")?,
+                '\t' => self.out.write_str("\\t")?,
+                '\r' => self.out.write_str("\\r")?,
+                '\u{08}' => self.out.write_str("\\b")?,
+                '\u{0C}' => self.out.write_str("\\f")?,
+                c if c.is_control() || self.options.ascii_only && !c.is_ascii() => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        write!(self.out, "\\u{unit:04x}")?;
+                    }
+                }
+                c => self.out.write_char(c)?,
+            }
+        }
+        self.out.write_char('"')
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        let name = match c {
+            '\n' => "newline",
+            ' ' => "space",
+            '\t' => "tab",
+            '\u{08}' => "backspace",
+            '\u{0C}' => "formfeed",
+            '\r' => "return",
+            _ => "",
+        };
+        if self.options.named_chars && !name.is_empty() {
+            return write!(self.out, "\\{name}");
+        }
+        let escape =
+            c.is_control() || c.is_whitespace() || self.options.ascii_only && !c.is_ascii();
+        if escape && c <= '\u{FFFF}' {
+            write!(self.out, "\\u{:04x}", c as u32)
+        } else {
+            write!(self.out, "\\{c}")
+        }
+    }
+
+    /// Prints floats the way Clojure does: with a fractional part, in scientific notation
+    /// outside `1e-3..1e7` and with `##` for the symbolic values.
+    fn write_float(&mut self, n: f64) -> fmt::Result {
+        if n.is_nan() {
+            return self.out.write_str("##NaN");
+        }
+        if n.is_infinite() {
+            return self.out.write_str(if n > 0.0 { "##Inf" } else { "##-Inf" });
+        }
+        // `-0.0` equals `0.0`.
+        let n = if self.options.canonical && n == 0.0 {
+            0.0
+        } else {
+            n
+        };
+        let (mantissa, exponent) = if n == 0.0 || (1e-3..1e7).contains(&n.abs()) {
+            (n.to_string(), None)
+        } else {
+            let s = format!("{n:e}");
+            let (mantissa, exponent) = s.split_once('e').unwrap();
+            (mantissa.to_string(), Some(exponent.to_string()))
+        };
+        self.out.write_str(&mantissa)?;
+        if !mantissa.contains('.') {
+            self.out.write_str(".0")?;
+        }
+        match exponent {
+            Some(exponent) => write!(self.out, "E{exponent}"),
+            None => Ok(()),
+        }
+    }
+
+    /// Prints the digits of `n` without trailing zeros, since `1.0M` and `1.00M` are equal.
+    fn write_canonical_decimal(&mut self, n: &BigDecimal) -> fmt::Result {
+        let (digits, scale) = n.normalized().into_bigint_and_exponent();
+        if digits.is_negative() {
+            self.out.write_char('-')?;
+        }
+        let digits = digits.magnitude().to_string();
+        // Zeros are only padded up to a bound, so that `1E300000000M` stays short.
+        if scale.unsigned_abs() > MAX_CANONICAL_PADDING {
+            write!(self.out, "{digits}E{}", -scale)?;
+        } else if scale <= 0 {
+            self.out.write_str(&digits)?;
+            self.out
+                .write_str(&"0".repeat(scale.unsigned_abs() as usize))?;
+        } else {
+            let digits = format!("{digits:0>width$}", width = scale as usize + 1);
+            let (int, frac) = digits.split_at(digits.len() - scale as usize);
+            write!(self.out, "{int}.{frac}")?;
+        }
+        self.out.write_char('M')
+    }
+
+    /// Writes `#` instead of the collection if it is nested too deep, otherwise calls `f`
+    /// one level deeper.
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> fmt::Result) -> fmt::Result {
+        if self
+            .options
+            .print_level
+            .is_some_and(|level| self.level >= level)
+        {
+            return self.out.write_char('#');
+        }
+        self.level += 1;
+        let ret = f(self);
+        self.level -= 1;
+        ret
+    }
+
+    fn write_seq<'e>(
+        &mut self,
+        open: &str,
+        items: impl IntoIterator<Item = &'e Edn>,
+        close: &str,
+    ) -> fmt::Result {
+        self.nested(|w| {
+            w.out.write_str(open)?;
+            for (i, item) in items.into_iter().enumerate() {
+                if i > 0 {
+                    w.out.write_char(' ')?;
+                }
+                if w.options.print_length == Some(i) {
+                    w.out.write_str("...")?;
+                    break;
+                }
+                w.write(item)?;
+            }
+            w.out.write_str(close)
+        })
+    }
+
+    /// The namespace shared by all keys of `map`, if it should be printed as `#:ns{}`.
+    fn map_namespace<'m>(&self, map: &'m BTreeMap<Edn, Edn>) -> Option<&'m str> {
+        if !self.options.namespace_maps {
+            return None;
+        }
+        let mut namespace = None;
+        for key in map.keys() {
+            let (ns, name) = match key {
+                Edn::Keyword(k) => (k.namespace()?, k.name()),
+                Edn::Symbol(s) => (s.namespace()?, s.name()),
+                _ => return None,
+            };
+            // `a/b/c` would read back from `#:a{b/c 1}` as `b/c`, `a/nil` as `nil`, and
+            // `#:nil{}` does not read.
+            if name.contains('/')
+                || matches!(key, Edn::Symbol(_)) && is_symbolic_literal(name)
+                || is_symbolic_literal(ns)
+            {
+                return None;
+            }
+            if namespace.is_some_and(|namespace| namespace != ns) {
+                return None;
+            }
+            namespace = Some(ns);
+        }
+        namespace
+    }
+
+    fn write_map(&mut self, map: &BTreeMap<Edn, Edn>) -> fmt::Result {
+        self.nested(|w| {
+            let namespace = w.map_namespace(map);
+            if let Some(ns) = namespace {
+                write!(w.out, "#:{ns}")?;
+            }
+            w.out.write_char('{')?;
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    w.out
+                        .write_str(if w.options.map_commas { ", " } else { " " })?;
+                }
+                if w.options.print_length == Some(i) {
+                    w.out.write_str("...")?;
+                    break;
+                }
+                match (namespace, key) {
+                    (Some(_), Edn::Keyword(k)) => write!(w.out, ":{}", k.name())?,
+                    (Some(_), Edn::Symbol(s)) => w.out.write_str(s.name())?,
+                    _ => w.write(key)?,
+                }
+                w.out.write_char(' ')?;
+                w.write(value)?;
+            }
+            w.out.write_char('}')
+        })
+    }
+}
+
+/// Prints `edn` with `options`, failing on tags that would not read back.
+pub fn to_string(edn: &Edn, options: &PrintOptions) -> Result<String, fmt::Error> {
+    let mut writer = Writer::new(String::new()).with_options(*options);
+    writer.write(edn)?;
+    Ok(writer.into_inner())
+}
+
+/// Canonical decimals with an exponent beyond this are printed like `1E300M`.
+const MAX_CANONICAL_PADDING: u64 = 20;
+
+/// Whether `#tag` reads as a tag, which is a symbol starting with a letter.
+pub(crate) fn is_tag(tag: &str) -> bool {
+    tag.starts_with(char::is_alphabetic) && matches!(read_symbolic(tag), Some(Edn::Symbol(_)))
+}
+
+/// Whether `#tag` reads back as an element tagged with `tag`, which is not the case for
+/// `inst` and `uuid` since the built-in readers read them.
+pub(crate) fn is_valid_tag(tag: &str) -> bool {
+    is_tag(tag) && builtin_tag_reader(tag).is_none()
+}
+
+/// Names the reader reads as values instead of symbols.
+fn is_symbolic_literal(name: &str) -> bool {
+    matches!(name, "nil" | "true" | "false")
+}
+
+/// Hashes everything written to it, to hash canonical output without building the string.
+struct HashWriter(Sha256);
+
+impl Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.update(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl Edn {
+    /// Prints the value with [`PrintOptions::canonical`].
+    pub fn to_canonical_string(&self) -> Result<String, fmt::Error> {
+        to_string(self, &PrintOptions::canonical())
+    }
+
+    /// The SHA-256 digest of [`Edn::to_canonical_string`], equal for equal values.
+    pub fn canonical_hash(&self) -> Result<[u8; 32], fmt::Error> {
+        let mut writer =
+            Writer::new(HashWriter(Sha256::new())).with_options(PrintOptions::canonical());
+        writer.write(self)?;
+        Ok(writer.into_inner().0.finalize().into())
+    }
+}
+
+/// Prints with the default [`PrintOptions`]. The alternate flag (`{:#}`) prints metadata,
+/// like `*print-meta*` in Clojure.
+///
+/// Fails on tags that would not read back like [`Writer::write`], so `to_string` panics on
+/// them. Use [`to_string`] to get the error instead.
+impl fmt::Display for Edn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = PrintOptions::new().print_meta(f.alternate());
+        Writer::new(f).with_options(options).write(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::edn_reader::{read_str_with_options, ReaderOptions};
+    use crate::edn_symbol::{Keyword, Symbol};
+
+    fn edn(s: &str) -> Edn {
+        read_str_with_options(s, ReaderOptions::new().keep_unknown_tags()).unwrap()
+    }
+
+    fn print(s: &str, options: PrintOptions) -> String {
+        to_string(&edn(s), &options).unwrap()
+    }
+
+    #[test]
+    fn test_numbers() {
+        for (input, output) in [
+            ("1.0", "1.0"),
+            ("-0.0", "-0.0"),
+            ("0.001", "0.001"),
+            ("1234567.5", "1234567.5"),
+            ("12345678.0", "1.2345678E7"),
+            ("1.5e-7", "1.5E-7"),
+            ("1e21", "1.0E21"),
+            ("##NaN", "##NaN"),
+            ("##-Inf", "##-Inf"),
+            ("2/4", "1/2"),
+            ("10N", "10N"),
+            ("1.50M", "1.50M"),
+        ] {
+            assert_eq!(edn(input).to_string(), output, "{input}");
+        }
+        assert_eq!(
+            Edn::BigRational(num::BigRational::from_integer(5.into())).to_string(),
+            "5/1"
+        );
+    }
+
+    #[test]
+    fn test_chars_and_strings() {
+        assert_eq!(
+            edn(r#"[\newline \space \tab \u0000 \é \☃ "a\"b\\c\nd\u0001é😀"]"#).to_string(),
+            r#"[\newline \space \tab \u0000 \é \☃ "a\"b\\c\nd\u0001é😀"]"#
+        );
+        assert_eq!(
+            print(
+                r#"[\newline \é "é😀"]"#,
+                PrintOptions::new().named_chars(false).ascii_only(true)
+            ),
+            r#"[\u000a \u00e9 "\u00e9\ud83d\ude00"]"#
+        );
+    }
+
+    #[test]
+    fn test_maps() {
+        assert_eq!(print("{:a 1 :b 2}", PrintOptions::new()), "{:a 1, :b 2}");
+        assert_eq!(
+            print("{:a 1 :b 2}", PrintOptions::new().map_commas(false)),
+            "{:a 1 :b 2}"
+        );
+        let options = PrintOptions::new().namespace_maps(true);
+        assert_eq!(print("{:x/a 1 x/b 2}", options), "#:x{b 2, :a 1}");
+        assert_eq!(print("{:x/a 1 :y/b 2}", options), "{:x/a 1, :y/b 2}");
+        assert_eq!(print("{:x/a 1 :b 2}", options), "{:b 2, :x/a 1}");
+        assert_eq!(print("{}", options), "{}");
+        for symbol in ["nil", "true", "false"] {
+            let map = format!("{{x/{symbol} 1 x/a 2}}");
+            assert_eq!(print(&map, options), format!("{{x/a 2, x/{symbol} 1}}"));
+        }
+        assert_eq!(print("{:x/nil 1}", options), "#:x{:nil 1}");
+        assert_eq!(print("{:nil/a 1}", options), "{:nil/a 1}");
+    }
+
+    #[test]
+    fn test_truncation() {
+        let options = PrintOptions::new().print_length(2);
+        assert_eq!(print("[1 2 3]", options), "[1 2 ...]");
+        assert_eq!(print("[1 2]", options), "[1 2]");
+        assert_eq!(print("{:a 1 :b 2 :c 3}", options), "{:a 1, :b 2, ...}");
+        let options = PrintOptions::new().print_level(2);
+        assert_eq!(print("[1 [2 [3 #{4}]]]", options), "[1 [2 #]]");
+        assert_eq!(print("[1]", PrintOptions::new().print_level(0)), "#");
+    }
+
+    #[test]
+    fn test_meta() {
+        let value = edn("^:private [^{:tag String} x]");
+        assert_eq!(value.to_string(), "[x]");
+        assert_eq!(format!("{value:#}"), "^{:private true} [^{:tag String} x]");
+    }
+
+    #[test]
+    fn test_canonical() {
+        let canonical = |s: &str| edn(s).to_canonical_string().unwrap();
+        assert_eq!(
+            canonical(r#"^:meta {:b #{3 1 2}, :a [-0.0 +7 1.500M 1E+2M -0.05M "é\u0001"]}"#),
+            r#"{:a [0.0 7 1.5M 100M -0.05M "é\u0001"] :b #{1 2 3}}"#
+        );
+        assert_eq!(
+            canonical(r#"#inst "2024-01-01T01:00:00+01:00""#),
+            r#"#inst "2024-01-01T00:00:00.000-00:00""#
+        );
+        for (a, b) in [("1.0M", "1.00M"), ("0.0", "-0.0"), ("^:x [1]", "[1]")] {
+            assert_eq!(canonical(a), canonical(b));
+            assert_eq!(edn(a).canonical_hash(), edn(b).canonical_hash());
+        }
+        assert_ne!(edn("1").canonical_hash(), edn("1N").canonical_hash());
+
+        for (input, output) in [
+            ("1E300000000M", "1E300000000M"),
+            ("-25E-300000000M", "-25E-300000000M"),
+            ("1000E20M", "1E23M"),
+            ("1E20M", "100000000000000000000M"),
+            ("1E-20M", "0.00000000000000000001M"),
+            ("123E-21M", "123E-21M"),
+        ] {
+            assert_eq!(canonical(input), output);
+            assert_eq!(canonical(output), output);
+        }
+        assert_eq!(
+            edn("1E300000000M").canonical_hash(),
+            edn("10E299999999M").canonical_hash()
+        );
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        let tagged = |tag: &str| Edn::TaggedElement(tag.to_string(), Box::new(Edn::Int(1)));
+        for tag in ["", "1x", "-x", "a b", "a{", ":a", "nil", "inst", "uuid"] {
+            let value = Edn::Vec(vec![tagged(tag)]);
+            assert_eq!(
+                to_string(&value, &PrintOptions::new()),
+                Err(fmt::Error),
+                "{tag}"
+            );
+            assert_eq!(value.canonical_hash(), Err(fmt::Error), "{tag}");
+        }
+        assert_eq!(
+            to_string(&tagged("my.ns/tag-1"), &PrintOptions::new()),
+            Ok("#my.ns/tag-1 1".to_string())
+        );
+        assert_eq!(tagged("é").to_string(), "#é 1");
+    }
+
+    fn has_invalid_tag(edn: &Edn) -> bool {
+        match edn {
+            Edn::TaggedElement(tag, edn) => !is_valid_tag(tag) || has_invalid_tag(edn),
+            Edn::List(vec) | Edn::Vec(vec) => vec.iter().any(has_invalid_tag),
+            Edn::Set(set) => set.iter().any(has_invalid_tag),
+            Edn::Map(map) => map
+                .iter()
+                .any(|(key, value)| has_invalid_tag(key) || has_invalid_tag(value)),
+            _ => false,
+        }
+    }
+
+    fn options() -> impl Strategy<Value = PrintOptions> {
+        (any::<bool>(), any::<bool>(), any::<bool>(), any::<bool>()).prop_map(
+            |(named_chars, map_commas, namespace_maps, ascii_only)| {
+                PrintOptions::new()
+                    .named_chars(named_chars)
+                    .map_commas(map_commas)
+                    .namespace_maps(namespace_maps)
+                    .ascii_only(ascii_only)
+            },
+        )
+    }
+
+    fn name() -> impl Strategy<Value = String> {
+        let part = || prop_oneof!["[a-z]{1,3}", "nil|true|false"];
+        (part(), proptest::option::of(part())).prop_filter_map(
+            "nil, true and false are not symbols",
+            |(first, second)| match second {
+                Some(second) => Some(format!("{first}/{second}")),
+                None if is_symbolic_literal(&first) => None,
+                None => Some(first),
+            },
+        )
+    }
+
+    fn scalar() -> impl Strategy<Value = Edn> {
+        prop_oneof![
+            Just(Edn::Nil),
+            any::<bool>().prop_map(Edn::Bool),
+            any::<String>().prop_map(Edn::String),
+            any::<char>().prop_map(Edn::Char),
+            any::<i64>().prop_map(Edn::Int),
+            any::<f64>().prop_map(|n| Edn::Float(n.into())),
+            (any::<i64>(), 1..i64::MAX)
+                .prop_map(|(n, d)| Edn::BigRational(num::BigRational::new(n.into(), d.into()))),
+            name().prop_map(|s| Edn::Keyword(Keyword::new(&s).unwrap())),
+            name().prop_map(|s| Edn::Symbol(Symbol::new(&s).unwrap())),
+        ]
+    }
+
+    fn tag() -> impl Strategy<Value = String> {
+        prop_oneof![
+            4 => name(),
+            1 => prop_oneof![Just(""), Just("1x"), Just("a b"), Just("inst")].prop_map(String::from),
+        ]
+    }
+
+    fn value() -> impl Strategy<Value = Edn> {
+        scalar().prop_recursive(3, 32, 4, |inner| {
+            prop_oneof![
+                (tag(), inner.clone())
+                    .prop_map(|(tag, edn)| Edn::TaggedElement(tag, Box::new(edn))),
+                proptest::collection::vec(inner.clone(), 0..4).prop_map(Edn::List),
+                proptest::collection::vec(inner.clone(), 0..4).prop_map(Edn::Vec),
+                proptest::collection::btree_set(inner.clone(), 0..4).prop_map(Edn::Set),
+                proptest::collection::btree_map(inner.clone(), inner, 0..4).prop_map(Edn::Map),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(value in value(), options in options()) {
+            let printed = to_string(&value, &options);
+            prop_assert_eq!(printed.is_err(), has_invalid_tag(&value));
+            let Ok(printed) = printed else {
+                return Ok(());
+            };
+            let read = edn(&printed);
+            prop_assert_eq!(to_string(&read, &options), Ok(printed));
+            prop_assert_eq!(read, value);
+        }
+
+        #[test]
+        fn test_canonical_round_trip(value in value()) {
+            let Ok(printed) = value.to_canonical_string() else {
+                prop_assert!(has_invalid_tag(&value));
+                return Ok(());
+            };
+            let read = edn(&printed);
+            prop_assert_eq!(read.to_canonical_string(), Ok(printed));
+            prop_assert_eq!(read, value);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Conversion between [`Edn`] and JSON values.
+//!
+//! [`Mode::Natural`] gives the JSON a JavaScript program would expect and loses what JSON
+//! cannot express. [`Mode::Tagged`] wraps every value JSON has no type for in a single-entry
+//! object like `{"#kw": "ns/name"}`, so converting back gives the identical `Edn`.
+
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+
+use num::{BigInt, ToPrimitive};
+use serde_json::{Map, Number, Value};
+
+use crate::edn_reader::Edn;
+use crate::edn_symbol::{Keyword, Symbol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Keywords and symbols become strings without the `:`, lists and sets become arrays,
+    /// chars, instants and UUIDs become strings and other numbers become JSON numbers.
+    /// Tags and metadata are dropped and maps with other keys than strings, keywords and
+    /// symbols are rejected.
+    #[default]
+    Natural,
+    /// Like [`Mode::Natural`], but other map keys are printed as EDN.
+    NaturalStringifyKeys,
+    /// Lossless encoding, see the module documentation for the tags.
+    Tagged,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonError {
+    NonFiniteFloat(f64),
+    NonStringKey(Edn),
+    DuplicateKey(String),
+    UnknownTag(String),
+    InvalidTagged(String, Value),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::NonFiniteFloat(n) => write!(f, "JSON cannot represent {n}"),
+            JsonError::NonStringKey(key) => write!(f, "JSON object keys must be strings: {key}"),
+            JsonError::DuplicateKey(key) => write!(f, "Duplicate JSON object key: {key}"),
+            JsonError::UnknownTag(tag) => write!(f, "Unknown tag: {tag}"),
+            JsonError::InvalidTagged(tag, value) => write!(f, "Invalid value for {tag}: {value}"),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+const KEYWORD: &str = "#kw";
+const SYMBOL: &str = "#sym";
+const CHAR: &str = "#char";
+const BIGINT: &str = "#bigint";
+const FLOAT: &str = "#float";
+const BIGDECIMAL: &str = "#bigdec";
+const BIGRATIONAL: &str = "#ratio";
+const LIST: &str = "#list";
+const SET: &str = "#set";
+const MAP: &str = "#map";
+const INST: &str = "#inst";
+const UUID: &str = "#uuid";
+const TAGGED: &str = "#tag";
+const META: &str = "#meta";
+
+pub fn to_json(edn: &Edn, mode: Mode) -> Result<Value, JsonError> {
+    match mode {
+        Mode::Natural => to_natural(edn, false),
+        Mode::NaturalStringifyKeys => to_natural(edn, true),
+        Mode::Tagged => Ok(to_tagged(edn)),
+    }
+}
+
+/// Converts JSON to EDN. In the natural modes objects become maps with string keys.
+pub fn from_json(json: &Value, mode: Mode) -> Result<Edn, JsonError> {
+    match mode {
+        Mode::Natural | Mode::NaturalStringifyKeys => Ok(from_natural(json)),
+        Mode::Tagged => from_tagged(json),
+    }
+}
+
+fn float(n: f64) -> Result<Value, JsonError> {
+    Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or(JsonError::NonFiniteFloat(n))
+}
+
+fn big_int(n: &BigInt) -> Result<Value, JsonError> {
+    match (n.to_i64(), n.to_u64()) {
+        (Some(n), _) => Ok(n.into()),
+        (_, Some(n)) => Ok(n.into()),
+        _ => float(n.to_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+fn to_natural(edn: &Edn, stringify_keys: bool) -> Result<Value, JsonError> {
+    let array = |items: &mut dyn Iterator<Item = &Edn>| {
+        items
+            .map(|edn| to_natural(edn, stringify_keys))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
+    };
+    Ok(match edn {
+        Edn::Nil => Value::Null,
+        Edn::Bool(b) => Value::Bool(*b),
+        Edn::String(s) => Value::String(s.clone()),
+        Edn::Symbol(s) => Value::String(s.as_str().to_string()),
+        Edn::Keyword(s) => Value::String(s.as_str().to_string()),
+        Edn::Char(c) => Value::String(c.to_string()),
+        Edn::Int(n) => Value::from(*n),
+        Edn::BigInt(n) => big_int(n)?,
+        Edn::Float(n) => float(n.into_inner())?,
+        Edn::BigDecimal(n) => float(n.to_f64().unwrap_or(f64::NAN))?,
+        Edn::BigRational(n) => float(n.to_f64().unwrap_or(f64::NAN))?,
+        Edn::List(vec) | Edn::Vec(vec) => array(&mut vec.iter())?,
+        Edn::Set(set) => array(&mut set.iter())?,
+        Edn::Map(map) => {
+            let mut object = Map::new();
+            for (key, value) in map {
+                let key = match key.without_meta() {
+                    Edn::String(s) => s.clone(),
+                    Edn::Symbol(s) => s.as_str().to_string(),
+                    Edn::Keyword(s) => s.as_str().to_string(),
+                    key if stringify_keys => key.to_string(),
+                    key => return Err(JsonError::NonStringKey(key.clone())),
+                };
+                if object.contains_key(&key) {
+                    return Err(JsonError::DuplicateKey(key));
+                }
+                object.insert(key, to_natural(value, stringify_keys)?);
+            }
+            Value::Object(object)
+        }
+        Edn::Inst(inst) => Value::String(inst.to_string()),
+        Edn::Uuid(uuid) => Value::String(uuid.to_string()),
+        Edn::TaggedElement(_, edn) | Edn::WithMeta(_, edn) => to_natural(edn, stringify_keys)?,
+    })
+}
+
+fn from_natural(json: &Value) -> Edn {
+    match json {
+        Value::Null => Edn::Nil,
+        Value::Bool(b) => Edn::Bool(*b),
+        Value::Number(n) => from_number(n),
+        Value::String(s) => Edn::String(s.clone()),
+        Value::Array(vec) => Edn::Vec(vec.iter().map(from_natural).collect()),
+        Value::Object(object) => Edn::Map(
+            object
+                .iter()
+                .map(|(key, value)| (Edn::String(key.clone()), from_natural(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_number(n: &Number) -> Edn {
+    match (n.as_i64(), n.as_u64()) {
+        (Some(n), _) => Edn::Int(n),
+        (_, Some(n)) => Edn::BigInt(n.into()),
+        _ => Edn::Float(n.as_f64().unwrap_or(f64::NAN).into()),
+    }
+}
+
+fn tagged(tag: &str, value: Value) -> Value {
+    Value::Object(Map::from_iter([(tag.to_string(), value)]))
+}
+
+fn to_tagged(edn: &Edn) -> Value {
+    let array =
+        |items: &mut dyn Iterator<Item = &Edn>| Value::Array(items.map(to_tagged).collect());
+    match edn {
+        Edn::Nil => Value::Null,
+        Edn::Bool(b) => Value::Bool(*b),
+        Edn::String(s) => Value::String(s.clone()),
+        Edn::Char(c) => tagged(CHAR, c.to_string().into()),
+        Edn::Symbol(s) => tagged(SYMBOL, s.as_str().into()),
+        Edn::Keyword(s) => tagged(KEYWORD, s.as_str().into()),
+        Edn::Int(n) => Value::from(*n),
+        Edn::BigInt(n) => tagged(BIGINT, n.to_string().into()),
+        Edn::Float(n) => match Number::from_f64(n.into_inner()) {
+            Some(n) => Value::Number(n),
+            None if n.is_nan() => tagged(FLOAT, "NaN".into()),
+            None if n.into_inner() > 0.0 => tagged(FLOAT, "Inf".into()),
+            None => tagged(FLOAT, "-Inf".into()),
+        },
+        Edn::BigDecimal(n) => tagged(BIGDECIMAL, n.to_string().into()),
+        Edn::BigRational(n) => tagged(BIGRATIONAL, n.to_string().into()),
+        Edn::List(vec) => tagged(LIST, array(&mut vec.iter())),
+        Edn::Vec(vec) => array(&mut vec.iter()),
+        Edn::Set(set) => tagged(SET, array(&mut set.iter())),
+        Edn::Map(map) => {
+            let plain = map.keys().all(|key| matches!(key, Edn::String(_)))
+                && !(map.len() == 1
+                    && matches!(map.keys().next(), Some(Edn::String(s)) if s.starts_with('#')));
+            if plain {
+                Value::Object(
+                    map.iter()
+                        .filter_map(|(key, value)| match key {
+                            Edn::String(s) => Some((s.clone(), to_tagged(value))),
+                            _ => None,
+                        })
+                        .collect(),
+                )
+            } else {
+                let entries = map
+                    .iter()
+                    .map(|(key, value)| Value::Array(vec![to_tagged(key), to_tagged(value)]));
+                tagged(MAP, Value::Array(entries.collect()))
+            }
+        }
+        Edn::Inst(inst) => tagged(INST, inst.to_string().into()),
+        Edn::Uuid(uuid) => tagged(UUID, uuid.to_string().into()),
+        Edn::TaggedElement(tag, edn) => tagged(
+            TAGGED,
+            Value::Array(vec![tag.as_str().into(), to_tagged(edn)]),
+        ),
+        Edn::WithMeta(meta, edn) => {
+            let meta = to_tagged(&Edn::Map(meta.clone()));
+            tagged(META, Value::Array(vec![meta, to_tagged(edn)]))
+        }
+    }
+}
+
+fn from_tagged(json: &Value) -> Result<Edn, JsonError> {
+    let object = match json {
+        Value::Object(object) => object,
+        Value::Array(vec) => return Ok(Edn::Vec(from_tagged_items(vec)?)),
+        json => return Ok(from_natural(json)),
+    };
+    let mut entries = object.iter();
+    let (tag, value) = match (entries.next(), entries.next()) {
+        (Some((tag, value)), None) if tag.starts_with('#') => (tag.as_str(), value),
+        _ => {
+            return object
+                .iter()
+                .map(|(key, value)| Ok((Edn::String(key.clone()), from_tagged(value)?)))
+                .collect::<Result<_, _>>()
+                .map(Edn::Map);
+        }
+    };
+    let invalid = || JsonError::InvalidTagged(tag.to_string(), value.clone());
+    let string = || value.as_str().ok_or_else(invalid);
+    let items = || match value {
+        Value::Array(vec) => from_tagged_items(vec),
+        _ => Err(invalid()),
+    };
+    let pair = || match items()?.try_into() {
+        Ok::<[Edn; 2], _>(pair) => Ok(pair),
+        Err(_) => Err(invalid()),
+    };
+    Ok(match tag {
+        KEYWORD => Edn::Keyword(Keyword::new(string()?).map_err(|_| invalid())?),
+        SYMBOL => Edn::Symbol(Symbol::new(string()?).map_err(|_| invalid())?),
+        CHAR => {
+            let mut chars = string()?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Edn::Char(c),
+                _ => return Err(invalid()),
+            }
+        }
+        BIGINT => Edn::BigInt(string()?.parse().map_err(|_| invalid())?),
+        FLOAT => match string()? {
+            "NaN" => Edn::Float(f64::NAN.into()),
+            "Inf" => Edn::Float(f64::INFINITY.into()),
+            "-Inf" => Edn::Float(f64::NEG_INFINITY.into()),
+            _ => return Err(invalid()),
+        },
+        BIGDECIMAL => Edn::BigDecimal(string()?.parse().map_err(|_| invalid())?),
+        BIGRATIONAL => Edn::BigRational(string()?.parse().map_err(|_| invalid())?),
+        LIST => Edn::List(items()?),
+        SET => {
+            let mut set = BTreeSet::new();
+            for edn in items()? {
+                if !set.insert(edn) {
+                    return Err(invalid());
+                }
+            }
+            Edn::Set(set)
+        }
+        MAP => {
+            let mut map = BTreeMap::new();
+            for entry in items()? {
+                let Edn::Vec(entry) = entry else {
+                    return Err(invalid());
+                };
+                let Ok([key, value]) = <[Edn; 2]>::try_from(entry) else {
+                    return Err(invalid());
+                };
+                if map.insert(key, value).is_some() {
+                    return Err(invalid());
+                }
+            }
+            Edn::Map(map)
+        }
+        INST => Edn::Inst(string()?.parse().map_err(|_| invalid())?),
+        UUID => Edn::Uuid(string()?.parse().map_err(|_| invalid())?),
+        TAGGED => match pair()? {
+            [Edn::String(tag), edn] => Edn::TaggedElement(tag, Box::new(edn)),
+            _ => return Err(invalid()),
+        },
+        META => match pair()? {
+            [Edn::Map(meta), edn] => edn.with_meta(meta),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(JsonError::UnknownTag(tag.to_string())),
+    })
+}
+
+fn from_tagged_items(vec: &[Value]) -> Result<Vec<Edn>, JsonError> {
+    vec.iter().map(from_tagged).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::{read_str_with_options, ReaderOptions};
+
+    fn edn(s: &str) -> Edn {
+        read_str_with_options(s, ReaderOptions::new().keep_unknown_tags()).unwrap()
+    }
+
+    #[test]
+    fn test_natural() {
+        let value = edn(r#"{:a [1 2.5 #{x} (nil)], "b" \c, c/d #my/tag 9007199254740993N}"#);
+        assert_eq!(
+            to_json(&value, Mode::Natural).unwrap().to_string(),
+            r#"{"a":[1,2.5,["x"],[null]],"b":"c","c/d":9007199254740993}"#
+        );
+        assert_eq!(
+            to_json(&edn("{1 2}"), Mode::Natural),
+            Err(JsonError::NonStringKey(Edn::Int(1)))
+        );
+        assert_eq!(
+            to_json(&edn("{:a 1, \"a\" 2}"), Mode::Natural),
+            Err(JsonError::DuplicateKey("a".to_string()))
+        );
+        assert_eq!(
+            to_json(&edn("{[1 2] 3}"), Mode::NaturalStringifyKeys)
+                .unwrap()
+                .to_string(),
+            r#"{"[1 2]":3}"#
+        );
+        assert!(to_json(&edn("##NaN"), Mode::Natural).is_err());
+
+        let json: Value =
+            serde_json::from_str(r#"{"a": [1, 1.5, null, 18446744073709551615]}"#).unwrap();
+        assert_eq!(
+            from_json(&json, Mode::Natural),
+            Ok(edn(r#"{"a" [1 1.5 nil 18446744073709551615N]}"#))
+        );
+    }
+
+    #[test]
+    fn test_tagged_round_trip() {
+        let value = edn(
+            r##"^{:doc "x"} [nil true "s" \c sym ns/sym :kw :ns/kw 1 2N 1.5 1.0 -0.0 ##NaN ##Inf
+            ##-Inf 1.5M 1/3 (1 2) #{1 2} {:a 1} {"b" [2]} {"#kw" "not a keyword"} {}
+            #inst "1985-04-12T23:20:50.520-00:00" #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6"
+            #my/tag {[1] ^:meta y}]"##,
+        );
+        let json = to_json(&value, Mode::Tagged).unwrap();
+        let text = json.to_string();
+        let back = from_json(&serde_json::from_str(&text).unwrap(), Mode::Tagged).unwrap();
+        assert_eq!(format!("{back:#}"), format!("{value:#}"));
+        assert_eq!(
+            to_json(&edn(r#"[:a #{1} {"b" c}]"#), Mode::Tagged)
+                .unwrap()
+                .to_string(),
+            r##"[{"#kw":"a"},{"#set":[1]},{"b":{"#sym":"c"}}]"##
+        );
+        assert_eq!(
+            from_json(&serde_json::json!({"#nope": 1}), Mode::Tagged),
+            Err(JsonError::UnknownTag("#nope".to_string()))
+        );
+        for json in [
+            serde_json::json!({"#set": [1, 1]}),
+            serde_json::json!({"#map": [[1, "a"], [1, "b"]]}),
+        ] {
+            assert!(
+                matches!(
+                    from_json(&json, Mode::Tagged),
+                    Err(JsonError::InvalidTagged(..))
+                ),
+                "{json}"
+            );
+        }
+    }
+}
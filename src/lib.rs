@@ -0,0 +1,29 @@
+pub mod binary;
+pub mod cst;
+mod edn_access;
+pub mod edn_convert;
+pub mod edn_de;
+pub mod edn_error;
+pub mod edn_inst;
+mod edn_io;
+pub mod edn_macro;
+pub mod edn_reader;
+pub mod edn_ser;
+pub mod edn_span;
+pub mod edn_stream;
+pub mod edn_symbol;
+pub mod edn_uuid;
+pub mod edn_writer;
+pub mod json;
+pub mod pretty;
+pub mod transit;
+
+pub use edn_convert::ConversionError;
+pub use edn_de::{from_edn, from_reader, from_str};
+pub use edn_error::{EdnError, ErrorKind, Position, SerdeError};
+pub use edn_inst::Inst;
+pub use edn_reader::{Edn, Reader, ReaderOptions};
+pub use edn_ser::{to_edn, to_string};
+pub use edn_symbol::{Keyword, Symbol};
+pub use edn_uuid::Uuid;
+pub use edn_writer::{PrintOptions, Writer};
@@ -0,0 +1,311 @@
+//! Pretty printing of [`Edn`] values, in the layout of `clojure.pprint`.
+//!
+//! Values that fit in the remaining width are printed on one line like `Display for Edn`.
+//! Larger maps put one entry per line with the values aligned, larger collections of scalars
+//! fill each line and larger collections of collections put one element per line.
+
+use core::fmt::{self, Write};
+use std::collections::BTreeMap;
+
+use crate::edn_reader::Edn;
+use crate::edn_writer::is_valid_tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrettyOptions {
+    width: usize,
+    indent: usize,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        PrettyOptions {
+            width: 80,
+            indent: 2,
+        }
+    }
+}
+
+impl PrettyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum line width in chars. Lines only get longer when a single scalar does not fit.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Indentation of map values and tagged elements that do not fit after their key or tag.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+}
+
+/// Prints `edn` laid out in `options.width`, failing like `Display for Edn` on tags that
+/// would not read back.
+pub fn to_string(edn: &Edn, options: &PrettyOptions) -> Result<String, fmt::Error> {
+    let mut printer = Printer {
+        out: String::new(),
+        column: 0,
+        options,
+        invalid_tag: false,
+    };
+    printer.print(edn, 0);
+    match printer.invalid_tag {
+        true => Err(fmt::Error),
+        false => Ok(printer.out),
+    }
+}
+
+/// Writer that fails once more than `budget` chars are written.
+struct Limited {
+    out: String,
+    budget: usize,
+}
+
+impl Write for Limited {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = s.chars().count();
+        if len > self.budget || s.contains('\n') {
+            return Err(fmt::Error);
+        }
+        self.budget -= len;
+        self.out.push_str(s);
+        Ok(())
+    }
+}
+
+/// The one-line form of `edn` if it is at most `budget` chars wide.
+fn flat(edn: &Edn, budget: usize) -> Option<String> {
+    let mut limited = Limited {
+        out: String::new(),
+        budget,
+    };
+    write!(limited, "{edn}").ok()?;
+    Some(limited.out)
+}
+
+fn is_collection(edn: &Edn) -> bool {
+    matches!(
+        edn.without_meta(),
+        Edn::List(_) | Edn::Vec(_) | Edn::Set(_) | Edn::Map(_) | Edn::TaggedElement(_, _)
+    )
+}
+
+struct Printer<'o> {
+    out: String,
+    column: usize,
+    options: &'o PrettyOptions,
+    /// Whether a tag that would not read back was printed. Such tags never fit on a line,
+    /// so every one of them gets here through the `TaggedElement` arm of `print`.
+    invalid_tag: bool,
+}
+
+impl Printer<'_> {
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+        self.column += s.chars().count();
+    }
+
+    fn newline(&mut self, column: usize) {
+        self.out.push('\n');
+        self.out.extend(std::iter::repeat_n(' ', column));
+        self.column = column;
+    }
+
+    /// Room left on the line when `trailing` closing delimiters still have to follow.
+    fn budget(&self, trailing: usize) -> usize {
+        self.options
+            .width
+            .saturating_sub(self.column)
+            .saturating_sub(trailing)
+    }
+
+    fn print(&mut self, edn: &Edn, trailing: usize) {
+        let edn = edn.without_meta();
+        if let Some(flat) = flat(edn, self.budget(trailing)) {
+            self.push(&flat);
+            return;
+        }
+        match edn {
+            Edn::List(vec) => self.print_seq("(", vec, ")", trailing),
+            Edn::Vec(vec) => self.print_seq("[", vec, "]", trailing),
+            Edn::Set(set) => self.print_seq("#{", set, "}", trailing),
+            Edn::Map(map) => self.print_map(map, trailing),
+            Edn::TaggedElement(tag, edn) => {
+                self.invalid_tag |= !is_valid_tag(tag);
+                let start = self.column;
+                self.push(&format!("#{tag}"));
+                if !is_collection(edn) && flat(edn, self.budget(trailing + 1)).is_none() {
+                    self.newline(start + self.options.indent);
+                } else {
+                    self.push(" ");
+                }
+                self.print(edn, trailing);
+            }
+            edn => self.push(&edn.to_string()),
+        }
+    }
+
+    fn print_seq<'e>(
+        &mut self,
+        open: &str,
+        items: impl IntoIterator<Item = &'e Edn>,
+        close: &str,
+        trailing: usize,
+    ) {
+        let items = items.into_iter().collect::<Vec<_>>();
+        // Scalars fill the lines, anything containing collections gets a line per element.
+        let fill = !items.iter().any(|edn| is_collection(edn));
+        self.push(open);
+        let start = self.column;
+        for (i, item) in items.iter().enumerate() {
+            let closing = if i + 1 == items.len() {
+                trailing + close.len()
+            } else {
+                0
+            };
+            if i > 0 {
+                match flat(item, self.budget(closing + 1)) {
+                    Some(flat) if fill => {
+                        self.push(" ");
+                        self.push(&flat);
+                        continue;
+                    }
+                    _ => self.newline(start),
+                }
+            }
+            self.print(item, closing);
+        }
+        self.push(close);
+    }
+
+    fn print_map(&mut self, map: &BTreeMap<Edn, Edn>, trailing: usize) {
+        self.push("{");
+        let start = self.column;
+        let keys = map
+            .keys()
+            .map(|key| flat(key, self.budget(0)))
+            .collect::<Vec<_>>();
+        // Values line up when every key fits on a line.
+        let align = keys
+            .iter()
+            .map(|key| key.as_ref().map(|key| key.chars().count()))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|widths| widths.into_iter().max());
+        for (i, ((key, value), flat_key)) in map.iter().zip(keys).enumerate() {
+            let closing = if i + 1 == map.len() { trailing + 1 } else { 1 };
+            if i > 0 {
+                self.push(",");
+                self.newline(start);
+            }
+            match flat_key {
+                Some(flat_key) => self.push(&flat_key),
+                None => self.print(key, 0),
+            }
+            if let Some(align) = align.filter(|&align| self.column <= start + align) {
+                let padding = start + align - self.column;
+                self.push(&" ".repeat(padding + 1));
+            } else {
+                self.push(" ");
+            }
+            // Collections may start next to the key if there is room left for them, scalars
+            // that do not fit move down.
+            let fits = flat(value, self.budget(closing)).is_some();
+            let room = self.budget(closing) >= self.options.width / 2;
+            if !(fits || is_collection(value) && room) {
+                self.out.truncate(self.out.trim_end_matches(' ').len());
+                self.newline(start + self.options.indent);
+            }
+            self.print(value, closing);
+        }
+        self.push("}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::{read_str_with_options, ReaderOptions};
+
+    fn pretty(s: &str, width: usize) -> String {
+        let edn = read_str_with_options(s, ReaderOptions::new().keep_unknown_tags()).unwrap();
+        to_string(&edn, &PrettyOptions::new().width(width)).unwrap()
+    }
+
+    #[test]
+    fn test_short_values_stay_on_one_line() {
+        assert_eq!(pretty("{:a [1 2], :b #{3}}", 80), "{:a [1 2], :b #{3}}");
+        assert_eq!(pretty(r#""a long string""#, 5), r#""a long string""#);
+    }
+
+    #[test]
+    fn test_maps() {
+        assert_eq!(
+            pretty(
+                r#"{:name "edn", :version "1.0.0", :deps {org.clojure/clojure {:mvn/version "1.11.1"}}}"#,
+                40
+            ),
+            r#"{:deps    {org.clojure/clojure
+             {:mvn/version "1.11.1"}},
+ :name    "edn",
+ :version "1.0.0"}"#
+        );
+        assert_eq!(
+            pretty(
+                r#"{:a "a string that is too long to fit next to its key"}"#,
+                30
+            ),
+            r#"{:a
+   "a string that is too long to fit next to its key"}"#
+        );
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(
+            pretty("[1 2 3 4 5 6 7 8 9 10 11 12 13 14 15]", 20),
+            "[1 2 3 4 5 6 7 8 9\n 10 11 12 13 14 15]"
+        );
+        assert_eq!(
+            pretty("(#{:alpha :beta} [:gamma :delta] #tag (1 2))", 20),
+            "(#{:alpha :beta}\n [:gamma :delta]\n #tag (1 2))"
+        );
+        assert_eq!(
+            pretty("[[1 2 3 4 5 6] [7 8 9 10 11 12]]", 15),
+            "[[1 2 3 4 5 6]\n [7 8 9 10 11\n  12]]"
+        );
+    }
+
+    #[test]
+    fn test_output_reads_back() {
+        let text = r#"{:servers [{:host "a.example.com", :ports [80 443], :tags #{:web :prod}}
+                      {:host "b.example.com", :ports [8080], :meta #my/tag {:x (1 2 3)}}]}"#;
+        let edn = read_str_with_options(text, ReaderOptions::new().keep_unknown_tags()).unwrap();
+        for width in [0, 10, 30, 60, 200] {
+            let printed = to_string(&edn, &PrettyOptions::new().width(width)).unwrap();
+            let read = read_str_with_options(&printed, ReaderOptions::new().keep_unknown_tags());
+            assert_eq!(read, Ok(edn.clone()), "{printed}");
+            if width >= 60 {
+                assert!(
+                    printed.lines().all(|line| line.chars().count() <= width),
+                    "{printed}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        let tagged = Edn::TaggedElement("1x".to_string(), Box::new(Edn::Int(1)));
+        let edn = Edn::Vec(vec![Edn::Int(1), Edn::Vec(vec![tagged])]);
+        for width in [0, 80] {
+            assert_eq!(
+                to_string(&edn, &PrettyOptions::new().width(width)),
+                Err(fmt::Error)
+            );
+        }
+    }
+}
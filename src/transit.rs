@@ -0,0 +1,525 @@
+//! Reading and writing [`Edn`] as [Transit-JSON](https://github.com/cognitect/transit-format).
+//!
+//! [`to_string`] writes the compact encoding, with maps as `["^ ", k, v, ...]` arrays and
+//! repeated keywords, symbols, tags and map keys replaced by cache codes. [`to_string_verbose`]
+//! writes maps as JSON objects and never caches. [`from_str`] reads both. Transit has no
+//! metadata, so it is dropped.
+//!
+//! Tagged elements whose tag is one of the tags used for EDN types, like `#set [1]`, are
+//! written with a `'` before the tag so that they read back as tagged elements.
+
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+
+use num::{BigInt, BigRational};
+use serde_json::{Map, Number, Value};
+
+use crate::edn_inst::Inst;
+use crate::edn_reader::Edn;
+use crate::edn_symbol::{Keyword, Symbol};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransitError {
+    Json(String),
+    InvalidCacheCode(String),
+    InvalidValue(String),
+}
+
+impl fmt::Display for TransitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitError::Json(msg) => write!(f, "Invalid JSON: {msg}"),
+            TransitError::InvalidCacheCode(code) => write!(f, "Invalid cache code: {code}"),
+            TransitError::InvalidValue(s) => write!(f, "Invalid transit value: {s}"),
+        }
+    }
+}
+
+impl Error for TransitError {}
+
+const MAP_AS_ARRAY: &str = "^ ";
+const CACHE_CODE_DIGITS: usize = 44;
+const BASE_CHAR_INDEX: u8 = 48;
+const MAX_CACHE_ENTRIES: usize = CACHE_CODE_DIGITS * CACHE_CODE_DIGITS;
+const MIN_SIZE_CACHEABLE: usize = 4;
+// Integers outside this range are written as strings, so JavaScript reads them exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// The tags written for EDN types, see [`escape_tag`].
+const BUILTIN_TAGS: [&str; 5] = ["'", "list", "set", "cmap", "ratio"];
+
+/// The tag written for the tagged element `tag`, which must not clash with [`BUILTIN_TAGS`].
+fn escape_tag(tag: &str) -> String {
+    if BUILTIN_TAGS.contains(&tag) || tag.starts_with('\'') {
+        format!("'{tag}")
+    } else {
+        tag.to_string()
+    }
+}
+
+fn is_cacheable(s: &str, as_map_key: bool) -> bool {
+    s.len() >= MIN_SIZE_CACHEABLE
+        && (as_map_key || s.starts_with("~:") || s.starts_with("~$") || s.starts_with("~#"))
+}
+
+fn cache_code(index: usize) -> String {
+    let digit = |i: usize| char::from(i as u8 + BASE_CHAR_INDEX);
+    if index < CACHE_CODE_DIGITS {
+        format!("^{}", digit(index))
+    } else {
+        format!(
+            "^{}{}",
+            digit(index / CACHE_CODE_DIGITS),
+            digit(index % CACHE_CODE_DIGITS)
+        )
+    }
+}
+
+fn cache_index(code: &str) -> Option<usize> {
+    let digit = |b: u8| {
+        let i = b.checked_sub(BASE_CHAR_INDEX)? as usize;
+        (i < CACHE_CODE_DIGITS).then_some(i)
+    };
+    match code.as_bytes() {
+        [b'^', lo] => digit(*lo),
+        [b'^', hi, lo] => Some(digit(*hi)? * CACHE_CODE_DIGITS + digit(*lo)?),
+        _ => None,
+    }
+}
+
+/// Writes `edn` as compact Transit-JSON.
+pub fn to_string(edn: &Edn) -> String {
+    Writer {
+        cache: Some(HashMap::new()),
+    }
+    .write_root(edn)
+    .to_string()
+}
+
+/// Writes `edn` as verbose Transit-JSON, which is easier to read but larger.
+pub fn to_string_verbose(edn: &Edn) -> String {
+    Writer { cache: None }.write_root(edn).to_string()
+}
+
+/// Reads compact or verbose Transit-JSON. Values with tags other than the ones for EDN
+/// types become [`Edn::TaggedElement`]s.
+pub fn from_str(s: &str) -> Result<Edn, TransitError> {
+    let json: Value = serde_json::from_str(s).map_err(|e| TransitError::Json(e.to_string()))?;
+    Parser { cache: Vec::new() }.read(&json, false)
+}
+
+struct Writer {
+    cache: Option<HashMap<String, usize>>,
+}
+
+impl Writer {
+    fn emit_string(&mut self, s: String, as_map_key: bool) -> Value {
+        let Some(cache) = self.cache.as_mut().filter(|_| is_cacheable(&s, as_map_key)) else {
+            return Value::String(s);
+        };
+        if let Some(&index) = cache.get(&s) {
+            return Value::String(cache_code(index));
+        }
+        if cache.len() == MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(s.clone(), cache.len());
+        Value::String(s)
+    }
+
+    fn tagged(&mut self, tag: &str, value: impl FnOnce(&mut Self) -> Value) -> Value {
+        let tag = format!("~#{tag}");
+        if self.cache.is_none() {
+            let value = value(self);
+            return Value::Object(Map::from_iter([(tag, value)]));
+        }
+        let tag = self.emit_string(tag, false);
+        Value::Array(vec![tag, value(self)])
+    }
+
+    fn write_root(&mut self, edn: &Edn) -> Value {
+        match edn.without_meta() {
+            Edn::List(_)
+            | Edn::Vec(_)
+            | Edn::Set(_)
+            | Edn::Map(_)
+            | Edn::BigRational(_)
+            | Edn::TaggedElement(_, _) => self.write(edn),
+            edn => self.tagged("'", |w| w.write(edn)),
+        }
+    }
+
+    fn write_all<'e>(&mut self, items: impl Iterator<Item = &'e Edn>) -> Value {
+        Value::Array(items.map(|edn| self.write(edn)).collect())
+    }
+
+    /// The string form of a scalar, which is how scalars are written as map keys.
+    fn scalar_string(edn: &Edn) -> Option<String> {
+        Some(match edn {
+            Edn::Nil => "~_".to_string(),
+            Edn::Bool(b) => format!("~?{}", if *b { 't' } else { 'f' }),
+            Edn::String(s) if s.starts_with(['~', '^', '`']) => format!("~{s}"),
+            Edn::String(s) => s.clone(),
+            Edn::Char(c) => format!("~c{c}"),
+            Edn::Symbol(s) => format!("~${s}"),
+            Edn::Keyword(s) => format!("~:{}", s.as_str()),
+            Edn::Int(n) => format!("~i{n}"),
+            Edn::BigInt(n) => format!("~n{n}"),
+            Edn::Float(n) if n.is_nan() => "~zNaN".to_string(),
+            Edn::Float(n) if n.is_infinite() && n.into_inner() > 0.0 => "~zINF".to_string(),
+            Edn::Float(n) if n.is_infinite() => "~z-INF".to_string(),
+            Edn::Float(n) => format!("~d{:?}", n.into_inner()),
+            Edn::BigDecimal(n) => format!("~f{n}"),
+            Edn::Inst(inst) => inst_string(inst),
+            Edn::Uuid(uuid) => format!("~u{uuid}"),
+            Edn::WithMeta(_, edn) => Self::scalar_string(edn)?,
+            _ => return None,
+        })
+    }
+
+    fn write(&mut self, edn: &Edn) -> Value {
+        match edn {
+            Edn::Nil => Value::Null,
+            Edn::Bool(b) => Value::Bool(*b),
+            Edn::Int(n) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(n) => Value::from(*n),
+            Edn::Float(n) if n.is_finite() => Number::from_f64(n.into_inner())
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            Edn::BigRational(n) => self.tagged("ratio", |w| {
+                let parts = [n.numer(), n.denom()].map(|n| Edn::BigInt(n.clone()));
+                w.write_all(parts.iter())
+            }),
+            Edn::List(vec) => self.tagged("list", |w| w.write_all(vec.iter())),
+            Edn::Vec(vec) => self.write_all(vec.iter()),
+            Edn::Set(set) => self.tagged("set", |w| w.write_all(set.iter())),
+            Edn::Map(map) if map.keys().all(|key| Self::scalar_string(key).is_some()) => {
+                if self.cache.is_none() {
+                    let object = map
+                        .iter()
+                        .map(|(key, value)| (Self::scalar_string(key).unwrap(), self.write(value)));
+                    return Value::Object(object.collect());
+                }
+                let mut array = vec![Value::String(MAP_AS_ARRAY.to_string())];
+                for (key, value) in map {
+                    array.push(self.emit_string(Self::scalar_string(key).unwrap(), true));
+                    array.push(self.write(value));
+                }
+                Value::Array(array)
+            }
+            Edn::Map(map) => self.tagged("cmap", |w| {
+                let entries = map.iter().flat_map(|(key, value)| [key, value]);
+                w.write_all(entries)
+            }),
+            Edn::TaggedElement(tag, edn) => self.tagged(&escape_tag(tag), |w| w.write(edn)),
+            Edn::WithMeta(_, edn) => self.write(edn),
+            edn => {
+                let s = Self::scalar_string(edn).unwrap();
+                self.emit_string(s, false)
+            }
+        }
+    }
+}
+
+/// Instants are written as milliseconds since the epoch when that does not lose precision.
+fn inst_string(inst: &Inst) -> String {
+    if inst.subsec_nanos().is_multiple_of(1_000_000) {
+        let millis = inst.timestamp() * 1000 + i64::from(inst.subsec_nanos() / 1_000_000);
+        format!("~m{millis}")
+    } else {
+        let s = inst.to_string();
+        format!("~t{}Z", s.strip_suffix("-00:00").unwrap_or(&s))
+    }
+}
+
+struct Parser {
+    cache: Vec<String>,
+}
+
+impl Parser {
+    /// Replaces cache codes by the string they stand for and caches new strings.
+    fn resolve(&mut self, s: &str, as_map_key: bool) -> Result<String, TransitError> {
+        if s.starts_with('^') && s != MAP_AS_ARRAY {
+            return cache_index(s)
+                .and_then(|i| self.cache.get(i))
+                .cloned()
+                .ok_or_else(|| TransitError::InvalidCacheCode(s.to_string()));
+        }
+        if is_cacheable(s, as_map_key) {
+            if self.cache.len() == MAX_CACHE_ENTRIES {
+                self.cache.clear();
+            }
+            self.cache.push(s.to_string());
+        }
+        Ok(s.to_string())
+    }
+
+    fn read(&mut self, json: &Value, as_map_key: bool) -> Result<Edn, TransitError> {
+        match json {
+            Value::Null => Ok(Edn::Nil),
+            Value::Bool(b) => Ok(Edn::Bool(*b)),
+            Value::Number(n) => Ok(match (n.as_i64(), n.as_u64()) {
+                (Some(n), _) => Edn::Int(n),
+                (_, Some(n)) => Edn::BigInt(n.into()),
+                _ => Edn::Float(n.as_f64().unwrap_or(f64::NAN).into()),
+            }),
+            Value::String(s) => {
+                let s = self.resolve(s, as_map_key)?;
+                read_scalar(&s)
+            }
+            Value::Array(vec) => self.read_array(vec),
+            Value::Object(object) => {
+                let mut entries = object.iter();
+                if let (Some((tag, value)), None) = (entries.next(), entries.next()) {
+                    if let Some(tag) = tag.strip_prefix("~#") {
+                        return self.read_tagged(tag, value);
+                    }
+                }
+                let mut map = BTreeMap::new();
+                for (key, value) in object {
+                    map.insert(read_scalar(key)?, self.read(value, false)?);
+                }
+                Ok(Edn::Map(map))
+            }
+        }
+    }
+
+    fn read_all(&mut self, items: &[Value]) -> Result<Vec<Edn>, TransitError> {
+        items.iter().map(|json| self.read(json, false)).collect()
+    }
+
+    fn read_array(&mut self, vec: &[Value]) -> Result<Edn, TransitError> {
+        let Some(Value::String(first)) = vec.first() else {
+            return self.read_all(vec).map(Edn::Vec);
+        };
+        let first = self.resolve(first, false)?;
+        if first == MAP_AS_ARRAY {
+            let mut map = BTreeMap::new();
+            for entry in vec[1..].chunks(2) {
+                let [key, value] = entry else {
+                    return Err(TransitError::InvalidValue("odd map".to_string()));
+                };
+                let key = self.read(key, true)?;
+                if map.insert(key.clone(), self.read(value, false)?).is_some() {
+                    return Err(TransitError::InvalidValue(format!(
+                        "duplicate map key {key}"
+                    )));
+                }
+            }
+            return Ok(Edn::Map(map));
+        }
+        if let (Some(tag), [_, value]) = (first.strip_prefix("~#"), vec) {
+            return self.read_tagged(tag, value);
+        }
+        let mut items = vec![read_scalar(&first)?];
+        items.extend(self.read_all(&vec[1..])?);
+        Ok(Edn::Vec(items))
+    }
+
+    fn read_tagged(&mut self, tag: &str, json: &Value) -> Result<Edn, TransitError> {
+        let invalid = || TransitError::InvalidValue(format!("~#{tag} {json}"));
+        let items = |parser: &mut Self| match json {
+            Value::Array(vec) => parser.read_all(vec),
+            _ => Err(invalid()),
+        };
+        Ok(match tag {
+            "'" => self.read(json, false)?,
+            "list" => Edn::List(items(self)?),
+            "set" => {
+                let mut set = BTreeSet::new();
+                for edn in items(self)? {
+                    if !set.insert(edn) {
+                        return Err(invalid());
+                    }
+                }
+                Edn::Set(set)
+            }
+            "cmap" => {
+                let items = items(self)?;
+                if items.len() % 2 != 0 {
+                    return Err(invalid());
+                }
+                let mut items = items.into_iter();
+                let mut map = BTreeMap::new();
+                while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                    if map.insert(key, value).is_some() {
+                        return Err(invalid());
+                    }
+                }
+                Edn::Map(map)
+            }
+            "ratio" => match <[Edn; 2]>::try_from(items(self)?) {
+                Ok([numer, denom]) => {
+                    let (Some(numer), Some(denom)) = (big_int(numer), big_int(denom)) else {
+                        return Err(invalid());
+                    };
+                    if denom == BigInt::from(0) {
+                        return Err(invalid());
+                    }
+                    Edn::BigRational(BigRational::new(numer, denom))
+                }
+                Err(_) => return Err(invalid()),
+            },
+            tag => {
+                let tag = tag.strip_prefix('\'').unwrap_or(tag);
+                Edn::TaggedElement(tag.to_string(), Box::new(self.read(json, false)?))
+            }
+        })
+    }
+}
+
+fn big_int(edn: Edn) -> Option<BigInt> {
+    match edn {
+        Edn::Int(n) => Some(n.into()),
+        Edn::BigInt(n) => Some(n),
+        _ => None,
+    }
+}
+
+fn read_scalar(s: &str) -> Result<Edn, TransitError> {
+    let invalid = || TransitError::InvalidValue(s.to_string());
+    let Some(rest) = s.strip_prefix('~') else {
+        return Ok(Edn::String(s.to_string()));
+    };
+    let mut chars = rest.chars();
+    let Some(tag) = chars.next() else {
+        return Err(invalid());
+    };
+    let rest = chars.as_str();
+    Ok(match tag {
+        '~' | '^' | '`' => Edn::String(format!("{tag}{rest}")),
+        '_' => Edn::Nil,
+        '?' => match rest {
+            "t" => Edn::Bool(true),
+            "f" => Edn::Bool(false),
+            _ => return Err(invalid()),
+        },
+        ':' => Edn::Keyword(Keyword::new(rest).map_err(|_| invalid())?),
+        '$' => Edn::Symbol(Symbol::new(rest).map_err(|_| invalid())?),
+        'i' => match rest.parse::<i64>() {
+            Ok(n) => Edn::Int(n),
+            Err(_) => Edn::BigInt(rest.parse().map_err(|_| invalid())?),
+        },
+        'n' => Edn::BigInt(rest.parse().map_err(|_| invalid())?),
+        'd' => Edn::Float(rest.parse::<f64>().map_err(|_| invalid())?.into()),
+        'f' => Edn::BigDecimal(rest.parse().map_err(|_| invalid())?),
+        'z' => Edn::Float(
+            match rest {
+                "NaN" => f64::NAN,
+                "INF" => f64::INFINITY,
+                "-INF" => f64::NEG_INFINITY,
+                _ => return Err(invalid()),
+            }
+            .into(),
+        ),
+        'c' => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Edn::Char(c),
+                _ => return Err(invalid()),
+            }
+        }
+        'm' => {
+            let millis: i64 = rest.parse().map_err(|_| invalid())?;
+            let nanos = millis.rem_euclid(1000) as u32 * 1_000_000;
+            Edn::Inst(Inst::from_timestamp(millis.div_euclid(1000), nanos).ok_or_else(invalid)?)
+        }
+        't' => Edn::Inst(rest.parse().map_err(|_| invalid())?),
+        'u' => Edn::Uuid(rest.parse().map_err(|_| invalid())?),
+        '#' => return Err(invalid()),
+        tag => Edn::TaggedElement(tag.to_string(), Box::new(Edn::String(rest.to_string()))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edn_reader::{read_str_with_options, ReaderOptions};
+
+    fn edn(s: &str) -> Edn {
+        read_str_with_options(s, ReaderOptions::new().keep_unknown_tags()).unwrap()
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(to_string(&edn(":kw")), r#"["~#'","~:kw"]"#);
+        assert_eq!(
+            to_string(&edn(r#"[{:name "a" :tags #{x}} {:name "~b" :tags #{}}]"#)),
+            r#"[["^ ","~:name","a","~:tags",["~#set",["~$x"]]],["^ ","^0","~~b","^1",["^2",[]]]]"#
+        );
+        assert_eq!(
+            to_string(&edn(
+                r#"[1/3 9007199254740992 #inst "1985-04-12T23:20:50.52Z" {[1] 2}]"#
+            )),
+            r#"[["~#ratio",["~n1","~n3"]],"~i9007199254740992","~m482196050520",["~#cmap",[[1],2]]]"#
+        );
+        assert_eq!(
+            to_string_verbose(&edn(r#"{:a #{1}, 2 (3)}"#)),
+            r#"{"~:a":{"~#set":[1]},"~i2":{"~#list":[3]}}"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = edn(
+            r##"[nil true "s" "~s" "^s" \c sym ns/sym :kw :ns/kw 1 -9007199254740993 2N 1.5
+            ##NaN ##Inf 1.5M 1/3 (1 2) #{1 2} {:a 1, "b" [2], nil 3, 4.5 5, \c 6}
+            {[1] 2, #{} 3} #inst "1985-04-12T23:20:50.520-00:00"
+            #inst "1985-04-12T23:20:50.123456789-00:00"
+            #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6" #my/tag {:x y}]"##,
+        );
+        for text in [to_string(&value), to_string_verbose(&value)] {
+            assert_eq!(
+                format!("{:#}", from_str(&text).unwrap()),
+                format!("{value:#}")
+            );
+        }
+
+        // Enough keywords to wrap the two-digit cache codes around.
+        let keywords = Edn::Vec(
+            (0..MAX_CACHE_ENTRIES + 10)
+                .map(|i| Edn::Keyword(Keyword::new(&format!("k{i}")).unwrap()))
+                .collect(),
+        );
+        let text = to_string(&Edn::Vec(vec![keywords.clone(), keywords.clone()]));
+        assert_eq!(
+            from_str(&text),
+            Ok(Edn::Vec(vec![keywords.clone(), keywords]))
+        );
+    }
+
+    #[test]
+    fn test_read_unknown_tags() {
+        assert_eq!(
+            from_str(r#"[["~#point",[1,2]],"~rhttp://example.com"]"#),
+            Ok(edn(r#"[#point [1 2] #r "http://example.com"]"#))
+        );
+        assert!(from_str(r#"["^0"]"#).is_err());
+    }
+
+    #[test]
+    fn test_builtin_tag_names() {
+        let mut value = edn("[#set [1 2] #list (1) #cmap {:a 1} #ratio [1 2] #sets #{}]");
+        if let Edn::Vec(items) = &mut value {
+            items.push(Edn::TaggedElement("'q".to_string(), Box::new(Edn::Nil)));
+        }
+        for text in [to_string(&value), to_string_verbose(&value)] {
+            assert_eq!(from_str(&text), Ok(value.clone()), "{text}");
+        }
+        assert_eq!(to_string_verbose(&edn("#set [1]")), r#"{"~#'set":[1]}"#);
+    }
+
+    #[test]
+    fn test_read_duplicates() {
+        for text in [
+            r#"["~#set",[1,1]]"#,
+            r#"["~#cmap",[[1],2,[1],3]]"#,
+            r#"["^ ","a",1,"a",2]"#,
+        ] {
+            assert!(
+                matches!(from_str(text), Err(TransitError::InvalidValue(_))),
+                "{text}"
+            );
+        }
+    }
+}